@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use similar::TextDiff;
+
+/// Default number of context lines around each change
+pub const DEFAULT_DIFF_CONTEXT: usize = 3;
+
+/// Unified diff between the original and the edited content of a file
+///
+/// Relative paths get git-style `a/` and `b/` prefixes in the `---` / `+++`
+/// headers; absolute paths are used as they are. A missing newline at the
+/// end of either side is marked with `\ No newline at end of file`.
+///
+/// # Arguments
+/// * `original` - Content before the edits
+/// * `edited` - Content after the edits
+/// * `path` - File path for the headers
+/// * `context_lines` - Unchanged lines to show around each change
+///
+/// # Returns
+/// * The diff, or an empty string if the contents are identical
+///
+/// # Example
+/// ```
+/// use llm_transform::unified_diff;
+/// let diff = unified_diff("a\nb\n", "a\nc\n", "file.txt", 3);
+/// assert_eq!(diff, "--- a/file.txt\n+++ b/file.txt\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n");
+/// ```
+pub fn unified_diff(original: &str, edited: &str, path: &str, context_lines: usize) -> String {
+    if original == edited {
+        return String::new();
+    }
+    let (old_header, new_header) = if Path::new(path).is_absolute() {
+        (path.to_string(), path.to_string())
+    } else {
+        (format!("a/{}", path), format!("b/{}", path))
+    };
+
+    TextDiff::from_lines(original, edited)
+        .unified_diff()
+        .context_radius(context_lines)
+        .header(&old_header, &new_header)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let original = "one\ntwo\nthree\nfour\nfive\n";
+        let edited = "one\ntwo\nTHREE\nfour\nfive\n";
+
+        assert_eq!(
+            unified_diff(original, edited, "src/x.rs", 1),
+            "--- a/src/x.rs\n+++ b/src/x.rs\n@@ -2,3 +2,3 @@\n two\n-three\n+THREE\n four\n"
+        );
+        assert!(
+            unified_diff(original, edited, "/tmp/x.rs", 0)
+                .starts_with("--- /tmp/x.rs\n+++ /tmp/x.rs\n@@ -3 +3 @@\n")
+        );
+        assert_eq!(unified_diff(original, original, "src/x.rs", 3), "");
+    }
+
+    #[test]
+    fn test_unified_diff_missing_newline() {
+        let diff = unified_diff("a\nb", "a\nc", "f", 3);
+        assert!(
+            diff.contains("-b\n\\ No newline at end of file\n+c\n\\ No newline at end of file\n"),
+            "{}",
+            diff
+        );
+
+        // The diff applies back through the patch parser
+        let patches = crate::patch::parse_patch(&diff).unwrap();
+        assert_eq!(patches[0].new_path.as_deref(), Some("f"));
+    }
+}
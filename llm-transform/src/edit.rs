@@ -1,5 +1,17 @@
+use crate::language::Language;
+use crate::position::{LineIndex, PositionError, Span};
+use crate::symbol::Symbol;
+use crate::syntax::{SyntaxError, new_syntax_errors};
+
 /// A text edit to apply to a file
-#[derive(Debug, Clone)]
+///
+/// An edit with `byte_start == byte_end` is a pure insertion of
+/// `replacement` at that offset.
+///
+/// `expected_text` and `expected_span_checksum` are optional per-edit
+/// preconditions: the edit is only applied if the bytes currently at
+/// `byte_start..byte_end` match them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Edit {
     /// Byte offset where the edit starts (inclusive)
     pub byte_start: usize,
@@ -7,8 +19,168 @@ pub struct Edit {
     pub byte_end: usize,
     /// Replacement text
     pub replacement: String,
-    /// Expected checksum of the file before this edit
+    /// Expected checksum of the file before this edit (or [`ANY_CHECKSUM`])
     pub expected_checksum: String,
+    /// Text the span must currently contain
+    pub expected_text: Option<String>,
+    /// BLAKE3 checksum (hex-encoded) the span's bytes must currently have
+    pub expected_span_checksum: Option<String>,
+    /// Text expected immediately before the span (checked, and used for relocation)
+    pub context_before: Option<String>,
+    /// Text expected immediately after the span (checked, and used for relocation)
+    pub context_after: Option<String>,
+}
+
+/// Whole-file checksum value that skips whole-file verification
+///
+/// Every edit must then carry a span-level precondition (`expected_text`,
+/// `expected_span_checksum` or a context around the span).
+pub const ANY_CHECKSUM: &str = "any";
+
+impl Edit {
+    /// Check if this edit is a pure insertion (zero-length span)
+    pub fn is_insertion(&self) -> bool {
+        self.byte_start == self.byte_end
+    }
+
+    /// Byte shift introduced by this edit (positive = content grows)
+    pub fn byte_shift(&self) -> i64 {
+        self.replacement.len() as i64 - (self.byte_end as i64 - self.byte_start as i64)
+    }
+
+    /// The byte span this edit replaces
+    pub fn span(&self) -> Span {
+        Span {
+            byte_start: self.byte_start,
+            byte_end: self.byte_end,
+        }
+    }
+
+    /// Check if two edits touch overlapping bytes
+    ///
+    /// Two replacements overlap if they share at least one byte. An insertion
+    /// overlaps a replacement only if it falls strictly inside it; insertions
+    /// at either end are well-defined (see [`sort_edits_descending`]). Two
+    /// insertions never overlap.
+    pub fn overlaps(&self, other: &Edit) -> bool {
+        match (self.is_insertion(), other.is_insertion()) {
+            (true, true) => false,
+            (true, false) => other.byte_start < self.byte_start && self.byte_start < other.byte_end,
+            (false, true) => self.byte_start < other.byte_start && other.byte_start < self.byte_end,
+            (false, false) => self.byte_start < other.byte_end && other.byte_start < self.byte_end,
+        }
+    }
+
+    /// Check if this edit carries a span-level precondition
+    ///
+    /// Context around the span counts too, which is what pins an insertion.
+    pub fn has_span_precondition(&self) -> bool {
+        self.expected_text.is_some()
+            || self.expected_span_checksum.is_some()
+            || self.context_before.is_some()
+            || self.context_after.is_some()
+    }
+
+    /// Check if the anchor (`context_before` + `expected_text` + `context_after`)
+    /// is at this edit's span in `content`
+    fn anchor_matches_at(&self, content: &str, byte_start: usize) -> bool {
+        let before = self.context_before.as_deref().unwrap_or("");
+        let text = self.expected_text.as_deref().unwrap_or("");
+        let after = self.context_after.as_deref().unwrap_or("");
+
+        let Some(anchor_start) = byte_start.checked_sub(before.len()) else {
+            return false;
+        };
+        let anchor_end = byte_start + text.len() + after.len();
+        content.get(anchor_start..anchor_end).is_some_and(|found| {
+            found.starts_with(before)
+                && found[before.len()..].starts_with(text)
+                && found.ends_with(after)
+        })
+    }
+
+    /// Check if two edits are identical (same span and replacement)
+    pub fn is_duplicate_of(&self, other: &Edit) -> bool {
+        self.byte_start == other.byte_start
+            && self.byte_end == other.byte_end
+            && self.replacement == other.replacement
+    }
+}
+
+/// Options controlling how a batch of edits is applied
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplyOptions {
+    /// Merge identical duplicate edits instead of rejecting them as overlapping.
+    /// Duplicates after the first are reported as skipped with reason "duplicate".
+    pub merge_duplicates: bool,
+    /// All-or-nothing: if any edit fails, every edit is rolled back and the
+    /// content is returned unchanged.
+    pub transactional: bool,
+    /// Report the checksum after each applied edit. Costs one full hash per
+    /// edit, so it is off by default.
+    pub intermediate_checksums: bool,
+    /// Widen spans that split a UTF-8 character to the nearest character
+    /// boundaries instead of rejecting them
+    pub snap_to_char_boundary: bool,
+    /// Relocate edits whose `expected_text` (plus context) moved, and
+    /// tolerate a whole-file checksum mismatch (every edit then needs
+    /// `expected_text`)
+    pub relocate: bool,
+    /// Maximum distance in bytes between the requested and the relocated
+    /// span start (`None` searches the whole content)
+    pub max_drift: Option<usize>,
+    /// Parse the content before and after the edits with this language's
+    /// grammar and reject the batch if the edits add parse errors (a
+    /// language without a grammar fails the batch as unsupported)
+    pub validate_syntax: Option<Language>,
+}
+
+impl ApplyOptions {
+    /// Enable or disable merging of identical duplicate edits
+    pub fn with_merge_duplicates(mut self, merge_duplicates: bool) -> Self {
+        self.merge_duplicates = merge_duplicates;
+        self
+    }
+
+    /// Enable or disable all-or-nothing application
+    pub fn with_transactional(mut self, transactional: bool) -> Self {
+        self.transactional = transactional;
+        self
+    }
+
+    /// Enable or disable per-edit intermediate checksums
+    pub fn with_intermediate_checksums(mut self, intermediate_checksums: bool) -> Self {
+        self.intermediate_checksums = intermediate_checksums;
+        self
+    }
+
+    /// Enable or disable snapping spans to character boundaries
+    pub fn with_snap_to_char_boundary(mut self, snap_to_char_boundary: bool) -> Self {
+        self.snap_to_char_boundary = snap_to_char_boundary;
+        self
+    }
+
+    /// Enable or disable anchor-based relocation, with an optional search window
+    pub fn with_relocate(mut self, relocate: bool, max_drift: Option<usize>) -> Self {
+        self.relocate = relocate;
+        self.max_drift = max_drift;
+        self
+    }
+
+    /// Enable syntax validation for `language`, or disable it with `None`
+    pub fn with_validate_syntax(mut self, language: Option<Language>) -> Self {
+        self.validate_syntax = language;
+        self
+    }
+}
+
+/// Where a relocated edit was actually applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Relocation {
+    /// Span the edit was applied to
+    pub span: Span,
+    /// Distance from the requested byte_start (positive = moved towards the end)
+    pub drift: i64,
 }
 
 /// Result of applying an edit
@@ -35,10 +207,12 @@ pub enum PerEditResult {
     Applied {
         /// Original byte offset of this edit
         byte_offset: usize,
-        /// New checksum after applying this edit
-        new_checksum: String,
+        /// New checksum after applying this edit (only with intermediate checksums enabled)
+        new_checksum: Option<String>,
         /// Byte shift introduced by this edit
         byte_shift: i64,
+        /// Set if the edit was relocated away from its requested span
+        relocation: Option<Relocation>,
     },
     /// Edit was skipped (checksum mismatch or other reason)
     Skipped {
@@ -53,6 +227,13 @@ pub enum PerEditResult {
         byte_offset: usize,
         /// Error message
         error: String,
+        /// Candidate spans, for relocation errors
+        candidates: Vec<Span>,
+    },
+    /// Edit was valid but undone because another edit in a transactional batch failed
+    RolledBack {
+        /// Original byte offset of this edit
+        byte_offset: usize,
     },
 }
 
@@ -61,6 +242,11 @@ pub enum PerEditResult {
 pub struct MultiEditResult {
     /// Individual edit results in application order
     pub edits: Vec<PerEditResult>,
+    /// Index in the input edits of each entry of `edits` (a batch that stops
+    /// at a failing edit has no result for the edits after it)
+    pub edit_indices: Vec<usize>,
+    /// Content after all applied edits
+    pub final_content: String,
     /// Final checksum after all applied edits
     pub final_checksum: String,
     /// Total byte shift across all edits
@@ -71,29 +257,64 @@ pub struct MultiEditResult {
     pub skipped_count: usize,
     /// Number of edits that failed
     pub error_count: usize,
+    /// Number of edits rolled back (transactional mode only)
+    pub rolled_back_count: usize,
+    /// Inverse edits that restore the original content, in post-edit
+    /// coordinates and expecting `final_checksum`
+    pub undo: Vec<Edit>,
 }
 
 impl MultiEditResult {
     /// Create a new multi-edit result from individual results
     pub fn new(
         edits: Vec<PerEditResult>,
+        final_content: String,
         final_checksum: String,
         total_byte_shift: i64,
     ) -> Self {
-        let applied_count = edits.iter().filter(|e| matches!(e, PerEditResult::Applied { .. })).count();
-        let skipped_count = edits.iter().filter(|e| matches!(e, PerEditResult::Skipped { .. })).count();
-        let error_count = edits.iter().filter(|e| matches!(e, PerEditResult::Error { .. })).count();
+        let applied_count = edits
+            .iter()
+            .filter(|e| matches!(e, PerEditResult::Applied { .. }))
+            .count();
+        let skipped_count = edits
+            .iter()
+            .filter(|e| matches!(e, PerEditResult::Skipped { .. }))
+            .count();
+        let error_count = edits
+            .iter()
+            .filter(|e| matches!(e, PerEditResult::Error { .. }))
+            .count();
+        let rolled_back_count = edits
+            .iter()
+            .filter(|e| matches!(e, PerEditResult::RolledBack { .. }))
+            .count();
 
         Self {
+            edit_indices: (0..edits.len()).collect(),
             edits,
+            final_content,
             final_checksum,
             total_byte_shift,
             applied_count,
             skipped_count,
             error_count,
+            rolled_back_count,
+            undo: Vec::new(),
         }
     }
 
+    /// Attach the inverse edit set
+    pub fn with_undo(mut self, undo: Vec<Edit>) -> Self {
+        self.undo = undo;
+        self
+    }
+
+    /// Attach the input edit index of each result
+    pub fn with_edit_indices(mut self, edit_indices: Vec<usize>) -> Self {
+        self.edit_indices = edit_indices;
+        self
+    }
+
     /// Check if all edits were applied successfully
     pub fn is_complete_success(&self) -> bool {
         self.applied_count == self.edits.len()
@@ -107,6 +328,15 @@ impl MultiEditResult {
 /// edits in forward order, earlier edits would shift the byte offsets of
 /// later edits, causing them to apply at wrong locations.
 ///
+/// # Ties at the same offset
+///
+/// * Several insertions at the same offset end up in request order: the
+///   first requested insertion appears first in the output. They are
+///   therefore applied in reverse request order.
+/// * An insertion at a replacement's `byte_start` lands before the
+///   replacement text (the replacement is applied first). An insertion at
+///   a replacement's `byte_end` lands after it, since its offset is higher.
+///
 /// # Arguments
 /// * `edits` - Slice of edits to sort
 ///
@@ -117,9 +347,9 @@ impl MultiEditResult {
 /// ```
 /// use llm_transform::{Edit, sort_edits_descending};
 /// let edits = vec![
-///     Edit { byte_start: 10, byte_end: 20, replacement: "a".to_string(), expected_checksum: "abc".to_string() },
-///     Edit { byte_start: 50, byte_end: 60, replacement: "b".to_string(), expected_checksum: "def".to_string() },
-///     Edit { byte_start: 30, byte_end: 40, replacement: "c".to_string(), expected_checksum: "ghi".to_string() },
+///     Edit { byte_start: 10, byte_end: 20, replacement: "a".to_string(), expected_checksum: "abc".to_string(), ..Default::default() },
+///     Edit { byte_start: 50, byte_end: 60, replacement: "b".to_string(), expected_checksum: "def".to_string(), ..Default::default() },
+///     Edit { byte_start: 30, byte_end: 40, replacement: "c".to_string(), expected_checksum: "ghi".to_string(), ..Default::default() },
 /// ];
 /// let sorted = sort_edits_descending(&edits);
 /// assert_eq!(sorted[0].byte_start, 50);
@@ -127,110 +357,584 @@ impl MultiEditResult {
 /// assert_eq!(sorted[2].byte_start, 10);
 /// ```
 pub fn sort_edits_descending(edits: &[Edit]) -> Vec<Edit> {
-    let mut sorted = edits.to_vec();
-    sorted.sort_by(|a, b| b.byte_start.cmp(&a.byte_start));
-    sorted
+    application_order(edits)
+        .into_iter()
+        .map(|index| edits[index].clone())
+        .collect()
+}
+
+/// Indices of `edits` in the order they are applied (see [`sort_edits_descending`])
+fn application_order(edits: &[Edit]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..edits.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (edit_a, edit_b) = (&edits[a], &edits[b]);
+        edit_b
+            .byte_start
+            .cmp(&edit_a.byte_start)
+            // Replacements before insertions at the same offset
+            .then_with(|| edit_a.is_insertion().cmp(&edit_b.is_insertion()))
+            // Later requests first, so earlier requests end up in front
+            .then_with(|| b.cmp(&a))
+    });
+    order
+}
+
+/// Check a batch of edits for overlapping spans
+///
+/// Runs before anything is applied, so an overlapping batch never touches
+/// the content. Edits listed in `ignored` (e.g. merged duplicates) are left
+/// out of the check.
+///
+/// # Arguments
+/// * `edits` - The edits to check
+/// * `ignored` - Per-edit flags for edits to leave out (empty slice for none)
+///
+/// # Returns
+/// * `Ok(())` if no two edits overlap
+/// * `Err(EditError::OverlappingEdits)` naming the first conflicting pair
+pub fn check_overlaps(edits: &[Edit], ignored: &[bool]) -> Result<(), EditError> {
+    let mut order: Vec<usize> = (0..edits.len())
+        .filter(|&i| !ignored.get(i).copied().unwrap_or(false))
+        .collect();
+    order.sort_by_key(|&i| (edits[i].byte_start, edits[i].byte_end));
+
+    // Sweep in ascending order, tracking the replacement that reaches furthest
+    let mut furthest: Option<usize> = None;
+    for &index in &order {
+        if let Some(prev) = furthest
+            && edits[prev].overlaps(&edits[index])
+        {
+            return Err(EditError::OverlappingEdits {
+                first_index: prev,
+                first_span: edits[prev].span(),
+                second_index: index,
+                second_span: edits[index].span(),
+            });
+        }
+
+        let edit = &edits[index];
+        if !edit.is_insertion() && furthest.is_none_or(|prev| edit.byte_end > edits[prev].byte_end)
+        {
+            furthest = Some(index);
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply multiple edits to file content in reverse byte order
+///
+/// Equivalent to [`apply_edits_with_options`] with default options.
+pub fn apply_edits(
+    content: &str,
+    initial_checksum: &str,
+    edits: &[Edit],
+) -> Result<MultiEditResult, EditError> {
+    apply_edits_with_options(content, initial_checksum, edits, &ApplyOptions::default())
 }
 
 /// Apply multiple edits to file content in reverse byte order
 ///
 /// This function:
-/// 1. Sorts edits by byte_start descending (to prevent position drift)
-/// 2. For each edit: verifies checksum, validates span, applies replacement
-/// 3. Tracks cumulative byte shift for offset adjustment
-/// 4. Returns detailed results for each edit
+/// 1. Verifies the initial checksum once
+/// 2. Optionally relocates edits whose anchor text moved (see [`relocate_edit`])
+/// 3. Checks the whole batch for overlapping edits before applying anything
+/// 4. Validates each edit's span, in byte_start descending order
+/// 5. Splices all valid edits into a pre-sized buffer in a single pass
+/// 6. Hashes the result once and returns detailed results for each edit,
+///    plus the final content
+///
+/// By default the batch stops at the first failing edit, keeping the edits
+/// applied before it. In transactional mode every edit is still checked,
+/// but if any of them fails the content is returned unchanged and the
+/// otherwise valid edits are reported as [`PerEditResult::RolledBack`].
 ///
 /// # Arguments
 /// * `content` - The file content to edit
 /// * `initial_checksum` - Expected checksum of content before any edits
 /// * `edits` - Edits to apply (will be sorted automatically)
+/// * `options` - Batch options (see [`ApplyOptions`])
 ///
 /// # Returns
-/// * `Ok(MultiEditResult)` - Results of batch edit operation
-/// * `Err(EditError)` - Error during batch operation
+/// * `Ok(MultiEditResult)` - Results of batch edit operation, including the edited content
+/// * `Err(EditError)` - Error during batch operation (checksum mismatch, overlapping edits)
+///
+/// # Relocation
+///
+/// With [`ApplyOptions::relocate`], a whole-file checksum mismatch is not an
+/// error. Each edit is instead located by its `expected_text` and optional
+/// context, and edits without a span-level precondition fail. Relocated
+/// edits report the span they were applied to and the drift.
 ///
 /// # Offset Adjustment
 ///
-/// Each edit in the batch carries an `expected_checksum`. After each applied edit,
-/// the checksum changes. For subsequent edits, we check against the UPDATED checksum.
-/// This allows each edit to validate against the state produced by previous edits.
+/// Edits are validated and reported in byte_start descending order, so
+/// edits at higher offsets come first and never shift the offsets of the
+/// edits below them. Since no two edits overlap, every edit's offsets refer
+/// to the original content. An edit's own `expected_checksum` must be empty,
+/// [`ANY_CHECKSUM`] or the batch's checksum (the content's checksum if
+/// `initial_checksum` is [`ANY_CHECKSUM`]); otherwise the edit fails with
+/// [`EditError::ChecksumMismatch`].
 ///
-/// The edits are sorted by byte_start descending, so edits at higher offsets
-/// are applied first. Lower offsets are unaffected by changes at higher offsets.
-pub fn apply_edits(
+/// # Cost
+///
+/// Linear in the content size plus the total replacement size. With
+/// [`ApplyOptions::intermediate_checksums`] the checksum after every edit is
+/// computed as well, which costs one full hash per applied edit.
+pub fn apply_edits_with_options(
     content: &str,
     initial_checksum: &str,
     edits: &[Edit],
+    options: &ApplyOptions,
 ) -> Result<MultiEditResult, EditError> {
-    // Verify initial state
-    verify_checksum(content, initial_checksum)?;
-
-    // Sort edits by byte_start descending
-    let sorted_edits = sort_edits_descending(edits);
-
-    let mut current_content = content.to_string();
-    let mut current_checksum = initial_checksum.to_string();
-    let mut total_byte_shift = 0i64;
-    let mut results = Vec::new();
-
-    for edit in sorted_edits.iter() {
-        // Update this edit's expected checksum to match current state
-        let mut adjusted_edit = edit.clone();
-        adjusted_edit.expected_checksum = current_checksum.clone();
-
-        match apply_edit(&current_content, &adjusted_edit) {
-            Ok(EditResult::Applied { new_checksum, byte_shift }) => {
-                // Calculate original byte offset for reporting
-                let original_offset = edit.byte_start;
-
-                // Apply the edit to get new content
-                let bytes = current_content.as_bytes();
-                let prefix = &bytes[0..edit.byte_start];
-                let suffix = &bytes[edit.byte_end..];
-                current_content = format!(
-                    "{}{}{}",
-                    String::from_utf8_lossy(prefix),
-                    edit.replacement,
-                    String::from_utf8_lossy(suffix)
-                );
+    if let Some(language) = options
+        .validate_syntax
+        .filter(|language| !language.has_grammar())
+    {
+        return Err(EditError::InvalidEditSpec(format!(
+            "syntax validation is not supported for {} files (no grammar)",
+            language
+        )));
+    }
+
+    // Verify initial state (unless the caller relies on span-level checks).
+    // In relocation mode a mismatch is tolerated; span checks take over.
+    let any_checksum = initial_checksum == ANY_CHECKSUM;
+    let checksum_verified = if any_checksum {
+        false
+    } else {
+        match verify_checksum(content, initial_checksum) {
+            Ok(()) => true,
+            Err(_) if options.relocate => false,
+            Err(e) => return Err(e),
+        }
+    };
+    let require_precondition = !checksum_verified;
+
+    // What each edit's own `expected_checksum` must match: the batch's
+    // checksum, or the content's if the batch accepts any
+    let batch_checksum = if any_checksum && !edits.iter().all(pins_any_checksum) {
+        blake3::hash(content.as_bytes()).to_hex().to_string()
+    } else {
+        initial_checksum.to_string()
+    };
+
+    let snapped: Vec<Edit>;
+    let edits = if options.snap_to_char_boundary {
+        snapped = edits
+            .iter()
+            .map(|edit| snap_to_char_boundary(edit, content))
+            .collect();
+        &snapped[..]
+    } else {
+        edits
+    };
+
+    // Move edits whose anchor text has drifted; failures become per-edit errors
+    let mut relocation_errors: Vec<Option<EditError>> = (0..edits.len()).map(|_| None).collect();
+    let relocated: Vec<Edit>;
+    let requested = edits;
+    let edits = if options.relocate {
+        relocated = edits
+            .iter()
+            .enumerate()
+            .map(
+                |(index, edit)| match relocate_edit(edit, content, options.max_drift) {
+                    Ok(moved) => moved,
+                    Err(e) => {
+                        relocation_errors[index] = Some(e);
+                        edit.clone()
+                    }
+                },
+            )
+            .collect();
+        &relocated[..]
+    } else {
+        edits
+    };
+
+    // Mark identical duplicates of an earlier edit, if merging is enabled
+    let mut duplicate = vec![false; edits.len()];
+    if options.merge_duplicates {
+        for i in 0..edits.len() {
+            duplicate[i] = edits[..i]
+                .iter()
+                .any(|earlier| earlier.is_duplicate_of(&edits[i]));
+        }
+    }
 
-                current_checksum = new_checksum.clone();
-                total_byte_shift += byte_shift;
+    // Reject overlapping spans before touching the content (edits that
+    // could not be relocated have no meaningful span)
+    let ignored: Vec<bool> = (0..edits.len())
+        .map(|i| duplicate[i] || relocation_errors[i].is_some())
+        .collect();
+    check_overlaps(edits, &ignored)?;
 
+    // Check every edit against the original content, in application order.
+    // Edits don't overlap, so each one sees the same bytes it would see if
+    // the edits above it had already been applied.
+    let mut results = Vec::with_capacity(edits.len());
+    let mut applied = Vec::with_capacity(edits.len());
+    let mut edit_indices = Vec::with_capacity(edits.len());
+    for index in application_order(edits) {
+        let edit = &edits[index];
+        edit_indices.push(index);
+
+        if duplicate[index] {
+            results.push(PerEditResult::Skipped {
+                byte_offset: edit.byte_start,
+                reason: "duplicate".to_string(),
+            });
+            continue;
+        }
+
+        let checked = match relocation_errors[index].take() {
+            Some(e) => Err(e),
+            None => check_edit(edit, content, &batch_checksum, require_precondition),
+        };
+
+        match checked {
+            Ok(()) => {
+                let original_offset = requested[index].byte_start;
+                let relocation = (edit.byte_start != original_offset).then(|| Relocation {
+                    span: edit.span(),
+                    drift: edit.byte_start as i64 - original_offset as i64,
+                });
+
+                applied.push(index);
                 results.push(PerEditResult::Applied {
                     byte_offset: original_offset,
-                    new_checksum,
-                    byte_shift,
-                });
-            }
-            Ok(EditResult::Skipped { reason }) => {
-                results.push(PerEditResult::Skipped {
-                    byte_offset: edit.byte_start,
-                    reason,
+                    new_checksum: None,
+                    byte_shift: edit.byte_shift(),
+                    relocation,
                 });
-                // Don't update content or checksum for skipped edits
             }
             Err(e) => {
                 results.push(PerEditResult::Error {
-                    byte_offset: edit.byte_start,
+                    byte_offset: requested[index].byte_start,
                     error: e.to_string(),
+                    candidates: e.candidates().to_vec(),
                 });
-                // Stop on first error - no rollback implemented yet
-                // (rollback will be a future enhancement)
-                return Ok(MultiEditResult::new(
-                    results,
-                    current_checksum,
-                    total_byte_shift,
-                ));
+                // Transactional batches keep going so every edit gets a status;
+                // otherwise stop on first error, keeping the edits before it
+                if !options.transactional {
+                    break;
+                }
+            }
+        }
+    }
+
+    if options.transactional
+        && results
+            .iter()
+            .any(|r| matches!(r, PerEditResult::Error { .. }))
+    {
+        let original_checksum = if checksum_verified {
+            initial_checksum.to_string()
+        } else {
+            blake3::hash(content.as_bytes()).to_hex().to_string()
+        };
+        return Ok(roll_back(results, content, &original_checksum).with_edit_indices(edit_indices));
+    }
+
+    if options.intermediate_checksums {
+        let mut checksums = intermediate_checksums(content, edits, &applied).into_iter();
+        for result in results.iter_mut() {
+            if let PerEditResult::Applied { new_checksum, .. } = result {
+                *new_checksum = checksums.next();
+            }
+        }
+    }
+
+    let total_byte_shift = applied.iter().map(|&i| edits[i].byte_shift()).sum();
+    let final_content = splice(content, edits, &applied);
+
+    // Reject a complete batch that breaks the syntax (a partial one is never written anyway)
+    let complete = !results
+        .iter()
+        .any(|r| matches!(r, PerEditResult::Error { .. }));
+    if let Some(language) = options.validate_syntax.filter(|_| complete) {
+        // Error positions in the edited content come from the original's
+        // line index, updated in place for the applied edits
+        let original_index = LineIndex::new(content);
+        let mut edited_index = original_index.clone();
+        edited_index.apply_edits(
+            content,
+            applied
+                .iter()
+                .map(|&i| (edits[i].span(), edits[i].replacement.as_str())),
+        );
+        let map_offset = |offset| map_through_edits(offset, edits, &applied);
+        let errors = new_syntax_errors(
+            content,
+            &original_index,
+            &final_content,
+            &edited_index,
+            language,
+            map_offset,
+        );
+        if !errors.is_empty() {
+            return Err(EditError::SyntaxRegression { errors });
+        }
+    }
+
+    let final_checksum = blake3::hash(final_content.as_bytes()).to_hex().to_string();
+    let undo = inverse_edits(content, edits, &applied, &final_checksum);
+
+    Ok(
+        MultiEditResult::new(results, final_content, final_checksum, total_byte_shift)
+            .with_undo(undo)
+            .with_edit_indices(edit_indices),
+    )
+}
+
+/// Offset in the edited content of an `offset` in the original (the start
+/// of the replacement if one of the `applied` edits replaced it)
+fn map_through_edits(offset: usize, edits: &[Edit], applied: &[usize]) -> usize {
+    let applied: Vec<&Edit> = applied.iter().map(|&index| &edits[index]).collect();
+    let offset = applied
+        .iter()
+        .find(|edit| edit.byte_start <= offset && offset < edit.byte_end)
+        .map_or(offset, |edit| edit.byte_start);
+    let shift: i64 = applied
+        .iter()
+        .filter(|edit| edit.byte_end <= offset)
+        .map(|edit| edit.byte_shift())
+        .sum();
+    (offset as i64 + shift) as usize
+}
+
+/// Build the edits that turn the edited content back into `content`
+///
+/// Each applied edit's replacement is located in post-edit coordinates and
+/// replaced by the original text. The inverse edits expect `final_checksum`
+/// and carry the inserted text as `expected_text`, so an undo only goes
+/// through while the edited text is still in place.
+fn inverse_edits(
+    content: &str,
+    edits: &[Edit],
+    applied: &[usize],
+    final_checksum: &str,
+) -> Vec<Edit> {
+    let mut shift = 0i64;
+
+    applied
+        .iter()
+        .rev()
+        .map(|&index| {
+            let edit = &edits[index];
+            let new_start = (edit.byte_start as i64 + shift) as usize;
+            shift += edit.byte_shift();
+
+            Edit {
+                byte_start: new_start,
+                byte_end: new_start + edit.replacement.len(),
+                replacement: content[edit.byte_start..edit.byte_end].to_string(),
+                expected_checksum: final_checksum.to_string(),
+                expected_text: Some(edit.replacement.clone()),
+                ..Default::default()
             }
+        })
+        .collect()
+}
+
+/// Find where an edit's anchor text is in `content` now
+///
+/// The anchor is `context_before` + `expected_text` + `context_after`. If it
+/// is still at the requested span, the edit is returned unchanged. Otherwise
+/// the content is searched for the anchor; candidates whose start is more
+/// than `max_drift` bytes from the requested `byte_start` are ignored. A
+/// single remaining candidate becomes the new span (the span keeps the
+/// length of `expected_text`).
+///
+/// Edits without `expected_text` are returned unchanged.
+///
+/// # Arguments
+/// * `edit` - The edit to relocate
+/// * `content` - The current file content
+/// * `max_drift` - Search window around the requested offset (`None` = whole content)
+///
+/// # Returns
+/// * `Ok(Edit)` - The edit, moved to where its anchor is now
+/// * `Err(EditError::AnchorNotFound)` - No candidate within the window
+///   (candidates outside the window are listed)
+/// * `Err(EditError::AnchorAmbiguous)` - More than one candidate within the window
+pub fn relocate_edit(
+    edit: &Edit,
+    content: &str,
+    max_drift: Option<usize>,
+) -> Result<Edit, EditError> {
+    let Some(text) = edit.expected_text.as_deref() else {
+        return Ok(edit.clone());
+    };
+
+    if edit.byte_end == edit.byte_start + text.len()
+        && edit.anchor_matches_at(content, edit.byte_start)
+    {
+        return Ok(edit.clone());
+    }
+
+    let before = edit.context_before.as_deref().unwrap_or("");
+    let after = edit.context_after.as_deref().unwrap_or("");
+    let anchor = format!("{}{}{}", before, text, after);
+    if anchor.is_empty() {
+        return Err(EditError::AnchorNotFound {
+            byte_start: edit.byte_start,
+            byte_end: edit.byte_end,
+            candidates: Vec::new(),
+        });
+    }
+
+    // Every occurrence of the anchor, overlapping ones included
+    let mut all = Vec::new();
+    let mut from = 0;
+    while let Some(found) = content[from..].find(&anchor) {
+        let anchor_start = from + found;
+        let byte_start = anchor_start + before.len();
+        all.push(Span {
+            byte_start,
+            byte_end: byte_start + text.len(),
+        });
+        from = ceil_char_boundary(content, anchor_start + 1);
+        if from >= content.len() {
+            break;
         }
     }
 
-    Ok(MultiEditResult::new(
+    let (near, far): (Vec<Span>, Vec<Span>) = all.into_iter().partition(|span| {
+        max_drift.is_none_or(|max| span.byte_start.abs_diff(edit.byte_start) <= max)
+    });
+
+    match near.as_slice() {
+        [span] => Ok(Edit {
+            byte_start: span.byte_start,
+            byte_end: span.byte_end,
+            ..edit.clone()
+        }),
+        [] => Err(EditError::AnchorNotFound {
+            byte_start: edit.byte_start,
+            byte_end: edit.byte_end,
+            candidates: far,
+        }),
+        _ => Err(EditError::AnchorAmbiguous {
+            byte_start: edit.byte_start,
+            byte_end: edit.byte_end,
+            candidates: near,
+        }),
+    }
+}
+
+/// Check one edit against the original content: its own checksum, span
+/// validity, then span preconditions
+///
+/// The edit's `expected_checksum` must be empty, [`ANY_CHECKSUM`] or
+/// `batch_checksum`. With `require_precondition` (whole-file checksum was
+/// [`ANY_CHECKSUM`]), an edit without a span-level precondition is rejected.
+fn check_edit(
+    edit: &Edit,
+    content: &str,
+    batch_checksum: &str,
+    require_precondition: bool,
+) -> Result<(), EditError> {
+    if !pins_any_checksum(edit) && edit.expected_checksum != batch_checksum {
+        return Err(EditError::ChecksumMismatch {
+            expected: edit.expected_checksum.clone(),
+            actual: batch_checksum.to_string(),
+        });
+    }
+
+    validate_edit_span(edit, content)?;
+
+    if require_precondition && !edit.has_span_precondition() {
+        return Err(EditError::MissingPrecondition {
+            byte_start: edit.byte_start,
+            byte_end: edit.byte_end,
+        });
+    }
+
+    verify_span(edit, content)
+}
+
+/// Whether an edit's own `expected_checksum` accepts any content (empty or [`ANY_CHECKSUM`])
+fn pins_any_checksum(edit: &Edit) -> bool {
+    edit.expected_checksum.is_empty() || edit.expected_checksum == ANY_CHECKSUM
+}
+
+/// Build the edited content in a single forward pass
+///
+/// `applied` holds edit indices in application (descending) order; walking it
+/// backwards visits the edits in ascending byte order.
+///
+/// Every applied edit has passed [`validate_edit_span`], so all offsets are
+/// in bounds and on character boundaries.
+fn splice(content: &str, edits: &[Edit], applied: &[usize]) -> String {
+    let total_byte_shift: i64 = applied.iter().map(|&i| edits[i].byte_shift()).sum();
+    let mut buffer =
+        String::with_capacity((content.len() as i64 + total_byte_shift).max(0) as usize);
+
+    let mut cursor = 0;
+    for &index in applied.iter().rev() {
+        let edit = &edits[index];
+        buffer.push_str(&content[cursor..edit.byte_start]);
+        buffer.push_str(&edit.replacement);
+        cursor = edit.byte_end;
+    }
+    buffer.push_str(&content[cursor..]);
+
+    buffer
+}
+
+/// Checksum of the content after each applied edit, in application order
+///
+/// After the k-th edit (descending order) the content is the untouched
+/// prefix up to that edit followed by the already edited tail, so each
+/// checksum is computed by streaming those pieces through the hasher
+/// without materializing the intermediate document. This is still one
+/// full hash per edit.
+fn intermediate_checksums(content: &str, edits: &[Edit], applied: &[usize]) -> Vec<String> {
+    let bytes = content.as_bytes();
+
+    (0..applied.len())
+        .map(|k| {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update(&bytes[..edits[applied[k]].byte_start]);
+
+            for j in (0..=k).rev() {
+                let edit = &edits[applied[j]];
+                let next_start = if j == 0 {
+                    bytes.len()
+                } else {
+                    edits[applied[j - 1]].byte_start
+                };
+                hasher.update(edit.replacement.as_bytes());
+                hasher.update(&bytes[edit.byte_end..next_start]);
+            }
+
+            hasher.finalize().to_hex().to_string()
+        })
+        .collect()
+}
+
+/// Undo a transactional batch: applied edits become rolled back, content is unchanged
+fn roll_back(
+    results: Vec<PerEditResult>,
+    content: &str,
+    initial_checksum: &str,
+) -> MultiEditResult {
+    let results = results
+        .into_iter()
+        .map(|r| match r {
+            PerEditResult::Applied { byte_offset, .. } => PerEditResult::RolledBack { byte_offset },
+            other => other,
+        })
+        .collect();
+
+    MultiEditResult::new(
         results,
-        current_checksum,
-        total_byte_shift,
-    ))
+        content.to_string(),
+        initial_checksum.to_string(),
+        0,
+    )
 }
 
 /// Error types for edit operations
@@ -242,43 +946,394 @@ pub enum EditError {
         byte_end: usize,
         content_len: usize,
     },
-    /// Invalid span (end <= start)
-    InvalidSpan {
+    /// Invalid span (end < start)
+    InvalidSpan { byte_start: usize, byte_end: usize },
+    /// Checksum verification failed
+    ChecksumMismatch { expected: String, actual: String },
+    /// Replacement text contains invalid UTF-8
+    InvalidReplacement,
+    /// Byte offset falls inside a multi-byte UTF-8 character
+    NotCharBoundary {
+        byte_offset: usize,
+        /// Nearest character boundary before the offset
+        prev_boundary: usize,
+        /// Nearest character boundary after the offset
+        next_boundary: usize,
+    },
+    /// Span text differs from the edit's `expected_text`
+    SpanTextMismatch {
         byte_start: usize,
         byte_end: usize,
+        expected: String,
+        actual: String,
     },
-    /// Checksum verification failed
-    ChecksumMismatch {
+    /// Span checksum differs from the edit's `expected_span_checksum`
+    SpanChecksumMismatch {
+        byte_start: usize,
+        byte_end: usize,
         expected: String,
         actual: String,
     },
-    /// Replacement text contains invalid UTF-8
-    InvalidReplacement,
+    /// Whole-file checksum was "any" but the edit has no span-level precondition
+    MissingPrecondition { byte_start: usize, byte_end: usize },
+    /// Relocation found no match for the edit's anchor near its requested span
+    AnchorNotFound {
+        byte_start: usize,
+        byte_end: usize,
+        /// Matches outside the allowed drift, if any
+        candidates: Vec<Span>,
+    },
+    /// Relocation found several matches for the edit's anchor
+    AnchorAmbiguous {
+        byte_start: usize,
+        byte_end: usize,
+        candidates: Vec<Span>,
+    },
+    /// Two edits in the same batch touch overlapping bytes
+    OverlappingEdits {
+        first_index: usize,
+        first_span: Span,
+        second_index: usize,
+        second_span: Span,
+    },
+    /// A line/column address could not be resolved to a byte offset
+    InvalidPosition(PositionError),
+    /// The edit's address fields are missing or contradictory
+    InvalidEditSpec(String),
+    /// A search-anchored edit did not find exactly the match it asked for
+    SearchMismatch {
+        find: String,
+        /// Requested occurrence (1-indexed), or `None` if the match had to be unique
+        occurrence: Option<usize>,
+        /// Every match in the content, in file order
+        matches: Vec<Span>,
+    },
+    /// A regex edit matched a different number of times than `expected_count`
+    MatchCountMismatch {
+        pattern: String,
+        expected: usize,
+        /// Every match in the content, in file order
+        matches: Vec<Span>,
+    },
+    /// A diff hunk was not found, even with fuzz, or matched several places equally well
+    HunkMismatch {
+        /// Line the hunk was expected at, if given
+        line: Option<usize>,
+        max_fuzz: usize,
+        /// Equally good matches (spans of the old text); empty if not found
+        candidates: Vec<Span>,
+    },
+    /// No item in the file matches a symbol path
+    SymbolNotFound {
+        symbol: String,
+        /// Items with the same or a similar name
+        candidates: Vec<Symbol>,
+    },
+    /// Several items match a symbol path
+    SymbolAmbiguous {
+        symbol: String,
+        candidates: Vec<Symbol>,
+    },
+    /// The edited content has parse errors the original did not have
+    SyntaxRegression {
+        /// The new errors, with positions in the edited content
+        errors: Vec<SyntaxError>,
+    },
 }
 
 impl std::fmt::Display for EditError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            EditError::OutOfBounds { byte_start, byte_end, content_len } => {
-                write!(f, "Byte span {}..{} out of bounds (content length: {})", byte_start, byte_end, content_len)
+            EditError::OutOfBounds {
+                byte_start,
+                byte_end,
+                content_len,
+            } => {
+                write!(
+                    f,
+                    "Byte span {}..{} out of bounds (content length: {})",
+                    byte_start, byte_end, content_len
+                )
             }
-            EditError::InvalidSpan { byte_start, byte_end } => {
-                write!(f, "Invalid span: end ({}) <= start ({})", byte_end, byte_start)
+            EditError::InvalidSpan {
+                byte_start,
+                byte_end,
+            } => {
+                write!(
+                    f,
+                    "Invalid span: end ({}) < start ({})",
+                    byte_end, byte_start
+                )
             }
             EditError::ChecksumMismatch { expected, actual } => {
-                write!(f, "Checksum mismatch: expected {}, got {}", expected, actual)
+                write!(
+                    f,
+                    "Checksum mismatch: expected {}, got {}",
+                    expected, actual
+                )
             }
             EditError::InvalidReplacement => {
                 write!(f, "Replacement text contains invalid UTF-8")
             }
+            EditError::NotCharBoundary {
+                byte_offset,
+                prev_boundary,
+                next_boundary,
+            } => {
+                write!(
+                    f,
+                    "Byte offset {} is inside a UTF-8 character (nearest boundaries: {} and {})",
+                    byte_offset, prev_boundary, next_boundary
+                )
+            }
+            EditError::SpanTextMismatch {
+                byte_start,
+                byte_end,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "Span {}..{} text mismatch: expected {:?}, found {:?}",
+                    byte_start, byte_end, expected, actual
+                )
+            }
+            EditError::SpanChecksumMismatch {
+                byte_start,
+                byte_end,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "Span {}..{} checksum mismatch: expected {}, got {}",
+                    byte_start, byte_end, expected, actual
+                )
+            }
+            EditError::MissingPrecondition {
+                byte_start,
+                byte_end,
+            } => {
+                write!(
+                    f,
+                    "Edit at {}..{} needs expected_text, expected_span_checksum or context when the whole-file checksum is not verified (\"any\" or relocation)",
+                    byte_start, byte_end
+                )
+            }
+            EditError::AnchorNotFound {
+                byte_start,
+                byte_end,
+                candidates,
+            } => {
+                write!(
+                    f,
+                    "Anchor for edit at {}..{} not found",
+                    byte_start, byte_end
+                )?;
+                if !candidates.is_empty() {
+                    write!(
+                        f,
+                        " within allowed drift ({} match(es) further away)",
+                        candidates.len()
+                    )?;
+                }
+                Ok(())
+            }
+            EditError::AnchorAmbiguous {
+                byte_start,
+                byte_end,
+                candidates,
+            } => {
+                write!(
+                    f,
+                    "Anchor for edit at {}..{} is ambiguous: {} candidates at byte(s) {}",
+                    byte_start,
+                    byte_end,
+                    candidates.len(),
+                    candidates
+                        .iter()
+                        .map(|c| c.byte_start.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+            EditError::OverlappingEdits {
+                first_index,
+                first_span,
+                second_index,
+                second_span,
+            } => {
+                write!(
+                    f,
+                    "Edit {} (bytes {}..{}) overlaps edit {} (bytes {}..{})",
+                    first_index,
+                    first_span.byte_start,
+                    first_span.byte_end,
+                    second_index,
+                    second_span.byte_start,
+                    second_span.byte_end
+                )
+            }
+            EditError::InvalidPosition(e) => write!(f, "Invalid position: {}", e),
+            EditError::InvalidEditSpec(msg) => write!(f, "Invalid edit: {}", msg),
+            EditError::SearchMismatch {
+                find,
+                occurrence,
+                matches,
+            } => {
+                match (occurrence, matches.len()) {
+                    (_, 0) => return write!(f, "Text {:?} not found", find),
+                    (None, count) => write!(f, "Text {:?} is not unique: {} matches", find, count)?,
+                    (Some(n), count) => write!(
+                        f,
+                        "Occurrence {} of text {:?} not found: {} match(es)",
+                        n, find, count
+                    )?,
+                }
+                write!(
+                    f,
+                    " at byte(s) {}",
+                    matches
+                        .iter()
+                        .map(|m| m.byte_start.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+            EditError::MatchCountMismatch {
+                pattern,
+                expected,
+                matches,
+            } => {
+                write!(
+                    f,
+                    "Pattern {:?} matched {} time(s), expected {}",
+                    pattern,
+                    matches.len(),
+                    expected
+                )?;
+                if !matches.is_empty() {
+                    write!(
+                        f,
+                        " (at byte(s) {})",
+                        matches
+                            .iter()
+                            .map(|m| m.byte_start.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )?;
+                }
+                Ok(())
+            }
+            EditError::HunkMismatch {
+                line,
+                max_fuzz,
+                candidates,
+            } => {
+                if candidates.is_empty() {
+                    write!(f, "Hunk not found (fuzz up to {})", max_fuzz)?;
+                } else {
+                    write!(
+                        f,
+                        "Hunk is ambiguous: {} equally close matches at byte(s) {}",
+                        candidates.len(),
+                        candidates
+                            .iter()
+                            .map(|c| c.byte_start.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )?;
+                }
+                if let Some(line) = line {
+                    write!(f, " near line {}", line)?;
+                }
+                Ok(())
+            }
+            EditError::SymbolNotFound { symbol, candidates } => {
+                write!(f, "Symbol {:?} not found", symbol)?;
+                if !candidates.is_empty() {
+                    write!(f, " (similar: {})", symbol_list(candidates))?;
+                }
+                Ok(())
+            }
+            EditError::SymbolAmbiguous { symbol, candidates } => {
+                write!(
+                    f,
+                    "Symbol {:?} is ambiguous: {} matches: {}",
+                    symbol,
+                    candidates.len(),
+                    symbol_list(candidates)
+                )
+            }
+            EditError::SyntaxRegression { errors } => {
+                write!(
+                    f,
+                    "Edits introduce {} syntax error(s): {}",
+                    errors.len(),
+                    errors
+                        .iter()
+                        .map(|e| e.to_string())
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                )
+            }
         }
     }
 }
 
 impl std::error::Error for EditError {}
 
+impl From<PositionError> for EditError {
+    fn from(e: PositionError) -> Self {
+        EditError::InvalidPosition(e)
+    }
+}
+
+impl EditError {
+    /// Candidate spans carried by the error (empty for most errors)
+    pub fn candidates(&self) -> &[Span] {
+        match self {
+            EditError::AnchorNotFound { candidates, .. }
+            | EditError::AnchorAmbiguous { candidates, .. }
+            | EditError::HunkMismatch { candidates, .. } => candidates,
+            EditError::SearchMismatch { matches, .. }
+            | EditError::MatchCountMismatch { matches, .. } => matches,
+            _ => &[],
+        }
+    }
+
+    /// Candidate symbols carried by a symbol error (empty for other errors)
+    pub fn symbol_candidates(&self) -> &[Symbol] {
+        match self {
+            EditError::SymbolNotFound { candidates, .. }
+            | EditError::SymbolAmbiguous { candidates, .. } => candidates,
+            _ => &[],
+        }
+    }
+
+    /// Parse errors carried by a syntax regression (empty for other errors)
+    pub fn syntax_errors(&self) -> &[SyntaxError] {
+        match self {
+            EditError::SyntaxRegression { errors } => errors,
+            _ => &[],
+        }
+    }
+}
+
+/// Symbol paths with their lines, for error messages
+fn symbol_list(symbols: &[Symbol]) -> String {
+    symbols
+        .iter()
+        .map(|symbol| format!("{} (line {})", symbol.path, symbol.line_start))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Validate an edit's byte span against file content
 ///
+/// Checks that the span is ordered, within bounds, and that both ends fall
+/// on UTF-8 character boundaries.
+///
 /// # Arguments
 /// * `edit` - The edit to validate
 /// * `content` - The file content to validate against
@@ -289,8 +1344,8 @@ impl std::error::Error for EditError {}
 pub fn validate_edit_span(edit: &Edit, content: &str) -> Result<(), EditError> {
     let content_len = content.len();
 
-    // Check span validity
-    if edit.byte_end <= edit.byte_start {
+    // Check span validity (an empty span is an insertion)
+    if edit.byte_end < edit.byte_start {
         return Err(EditError::InvalidSpan {
             byte_start: edit.byte_start,
             byte_end: edit.byte_end,
@@ -306,10 +1361,130 @@ pub fn validate_edit_span(edit: &Edit, content: &str) -> Result<(), EditError> {
         });
     }
 
+    // Never split a multi-byte character
+    for byte_offset in [edit.byte_start, edit.byte_end] {
+        if !content.is_char_boundary(byte_offset) {
+            return Err(EditError::NotCharBoundary {
+                byte_offset,
+                prev_boundary: floor_char_boundary(content, byte_offset),
+                next_boundary: ceil_char_boundary(content, byte_offset),
+            });
+        }
+    }
+
     Ok(())
 }
 
-/// Verify that file content matches the expected checksum
+/// Largest character boundary at or before `byte_offset` (clamped to the content length)
+pub fn floor_char_boundary(content: &str, byte_offset: usize) -> usize {
+    let mut offset = byte_offset.min(content.len());
+    while !content.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+/// Smallest character boundary at or after `byte_offset` (clamped to the content length)
+pub fn ceil_char_boundary(content: &str, byte_offset: usize) -> usize {
+    let mut offset = byte_offset.min(content.len());
+    while !content.is_char_boundary(offset) {
+        offset += 1;
+    }
+    offset
+}
+
+/// Widen an edit's span to the nearest character boundaries
+///
+/// `byte_start` moves down and `byte_end` moves up, so the span covers every
+/// character it touched. Out-of-bounds offsets are left alone so that
+/// validation still reports them.
+pub fn snap_to_char_boundary(edit: &Edit, content: &str) -> Edit {
+    let mut snapped = edit.clone();
+    if edit.byte_start <= content.len() {
+        snapped.byte_start = floor_char_boundary(content, edit.byte_start);
+    }
+    if edit.byte_end <= content.len() {
+        snapped.byte_end = ceil_char_boundary(content, edit.byte_end);
+    }
+    snapped
+}
+
+/// Verify an edit's span-level preconditions against the current content
+///
+/// Compares the bytes at `byte_start..byte_end` with `expected_text` and/or
+/// `expected_span_checksum`, and the bytes just before and after the span
+/// with `context_before` and `context_after`. Edits without preconditions
+/// always pass. The span must already be valid (see [`validate_edit_span`]).
+///
+/// # Arguments
+/// * `edit` - The edit whose preconditions to check
+/// * `content` - The current file content
+///
+/// # Returns
+/// * `Ok(())` if all preconditions hold
+/// * `Err(EditError::SpanTextMismatch)` or `Err(EditError::SpanChecksumMismatch)` otherwise
+pub fn verify_span(edit: &Edit, content: &str) -> Result<(), EditError> {
+    let actual = &content[edit.byte_start..edit.byte_end];
+
+    if let Some(expected) = &edit.expected_text
+        && expected != actual
+    {
+        return Err(EditError::SpanTextMismatch {
+            byte_start: edit.byte_start,
+            byte_end: edit.byte_end,
+            expected: expected.clone(),
+            actual: actual.to_string(),
+        });
+    }
+
+    if let Some(expected) = &edit.expected_span_checksum {
+        let actual_checksum = blake3::hash(actual.as_bytes()).to_hex().to_string();
+        if *expected != actual_checksum {
+            return Err(EditError::SpanChecksumMismatch {
+                byte_start: edit.byte_start,
+                byte_end: edit.byte_end,
+                expected: expected.clone(),
+                actual: actual_checksum,
+            });
+        }
+    }
+
+    if let Some(before) = &edit.context_before {
+        verify_context(
+            content,
+            edit.byte_start.saturating_sub(before.len()),
+            edit.byte_start,
+            before,
+        )?;
+    }
+    if let Some(after) = &edit.context_after {
+        let end = (edit.byte_end + after.len()).min(content.len());
+        verify_context(content, edit.byte_end, end, after)?;
+    }
+
+    Ok(())
+}
+
+/// Check that `content[byte_start..byte_end]` is the `expected` context
+fn verify_context(
+    content: &str,
+    byte_start: usize,
+    byte_end: usize,
+    expected: &str,
+) -> Result<(), EditError> {
+    let actual = &content.as_bytes()[byte_start..byte_end];
+    if actual == expected.as_bytes() {
+        return Ok(());
+    }
+    Err(EditError::SpanTextMismatch {
+        byte_start,
+        byte_end,
+        expected: expected.to_string(),
+        actual: String::from_utf8_lossy(actual).into_owned(),
+    })
+}
+
+/// Verify that file content matches the expected checksum
 ///
 /// # Arguments
 /// * `content` - The file content to verify
@@ -335,10 +1510,11 @@ pub fn verify_checksum(content: &str, expected_checksum: &str) -> Result<(), Edi
 /// Apply a single edit to file content
 ///
 /// This function:
-/// 1. Verifies the checksum matches
+/// 1. Verifies the checksum matches (skipped for [`ANY_CHECKSUM`])
 /// 2. Validates the byte span is within bounds
-/// 3. Applies the replacement
-/// 4. Computes the new checksum
+/// 3. Verifies span-level preconditions, if any
+/// 4. Applies the replacement
+/// 5. Computes the new checksum
 ///
 /// # Arguments
 /// * `content` - The file content to edit (borrowed)
@@ -349,35 +1525,24 @@ pub fn verify_checksum(content: &str, expected_checksum: &str) -> Result<(), Edi
 /// * `Err(EditError)` - Error during edit application
 pub fn apply_edit(content: &str, edit: &Edit) -> Result<EditResult, EditError> {
     // Step 1: Verify checksum
-    verify_checksum(content, &edit.expected_checksum)?;
-
-    // Step 2: Validate span bounds
-    validate_edit_span(edit, content)?;
+    let any_checksum = edit.expected_checksum == ANY_CHECKSUM;
+    if !any_checksum {
+        verify_checksum(content, &edit.expected_checksum)?;
+    }
 
-    // Step 3: Apply replacement
-    let bytes = content.as_bytes();
-    let prefix = &bytes[0..edit.byte_start];
-    let suffix = &bytes[edit.byte_end..];
+    // Steps 2-3: Validate span bounds and span preconditions
+    check_edit(edit, content, &edit.expected_checksum, any_checksum)?;
 
-    let new_content = format!(
-        "{}{}{}",
-        String::from_utf8_lossy(prefix),
-        edit.replacement,
-        String::from_utf8_lossy(suffix)
-    );
+    // Step 4: Apply replacement (span is on character boundaries)
+    let new_content = splice(content, std::slice::from_ref(edit), &[0]);
 
-    // Step 4: Compute new checksum
+    // Step 5: Compute new checksum
     let new_hash = blake3::hash(new_content.as_bytes());
     let new_checksum = new_hash.to_hex().to_string();
 
-    // Calculate byte shift
-    let old_span_len = edit.byte_end - edit.byte_start;
-    let new_span_len = edit.replacement.len();
-    let byte_shift = new_span_len as i64 - old_span_len as i64;
-
     Ok(EditResult::Applied {
         new_checksum,
-        byte_shift,
+        byte_shift: edit.byte_shift(),
     })
 }
 
@@ -392,19 +1557,17 @@ pub fn apply_edit(content: &str, edit: &Edit) -> Result<EditResult, EditError> {
 /// # Returns
 /// * `Ok(String)` - New content after edit
 /// * `Err(EditError)` - Error during edit application
-pub fn apply_edit_to_file(file_content: &crate::file::FileContent, edit: &Edit) -> Result<String, EditError> {
+pub fn apply_edit_to_file(
+    file_content: &crate::file::FileContent,
+    edit: &Edit,
+) -> Result<String, EditError> {
     apply_edit(&file_content.content, edit)?;
 
     // If we got here, apply_edit succeeded - redo the work to return new content
-    let bytes = file_content.content.as_bytes();
-    let prefix = &bytes[0..edit.byte_start];
-    let suffix = &bytes[edit.byte_end..];
-
-    Ok(format!(
-        "{}{}{}",
-        String::from_utf8_lossy(prefix),
-        edit.replacement,
-        String::from_utf8_lossy(suffix)
+    Ok(splice(
+        &file_content.content,
+        std::slice::from_ref(edit),
+        &[0],
     ))
 }
 
@@ -453,13 +1616,17 @@ mod tests {
             byte_end: 12,
             replacement: "Rust".to_string(),
             expected_checksum: checksum.clone(),
+            ..Default::default()
         };
 
         let result = apply_edit(content, &edit);
 
         assert!(result.is_ok());
         match result {
-            Ok(EditResult::Applied { new_checksum, byte_shift }) => {
+            Ok(EditResult::Applied {
+                new_checksum,
+                byte_shift,
+            }) => {
                 // "World" is 5 bytes, "Rust" is 4 bytes, so shift is -1
                 assert_eq!(byte_shift, -1);
                 // New checksum should be different
@@ -479,6 +1646,7 @@ mod tests {
             byte_end: 12,
             replacement: "Rust".to_string(),
             expected_checksum: wrong_checksum.to_string(),
+            ..Default::default()
         };
 
         let result = apply_edit(content, &edit);
@@ -500,18 +1668,21 @@ mod tests {
                 byte_end: 20,
                 replacement: "a".to_string(),
                 expected_checksum: "abc".to_string(),
+                ..Default::default()
             },
             Edit {
                 byte_start: 50,
                 byte_end: 60,
                 replacement: "b".to_string(),
                 expected_checksum: "def".to_string(),
+                ..Default::default()
             },
             Edit {
                 byte_start: 30,
                 byte_end: 40,
                 replacement: "c".to_string(),
                 expected_checksum: "ghi".to_string(),
+                ..Default::default()
             },
         ];
 
@@ -535,6 +1706,7 @@ mod tests {
             byte_end: 9,
             replacement: "slow".to_string(),
             expected_checksum: checksum.clone(),
+            ..Default::default()
         };
 
         // After first edit, the content changes and checksum changes
@@ -545,6 +1717,7 @@ mod tests {
             byte_end: 39,
             replacement: "active".to_string(),
             expected_checksum: checksum.clone(),
+            ..Default::default()
         };
 
         let edits = vec![edit1, edit2];
@@ -567,5 +1740,850 @@ mod tests {
 
         // Final checksum should be different from initial
         assert_ne!(multi_result.final_checksum, checksum);
+
+        // Final content reflects both edits and matches the final checksum
+        assert_eq!(
+            multi_result.final_content,
+            "The slow brown fox jumps over the active dog."
+        );
+        assert_eq!(
+            multi_result.final_checksum,
+            compute_checksum(&multi_result.final_content)
+        );
+    }
+
+    #[test]
+    fn test_validate_edit_span_insertion() {
+        let content = "Hello";
+        let insertion = Edit {
+            byte_start: 5,
+            byte_end: 5,
+            replacement: "!".to_string(),
+            expected_checksum: String::new(),
+            ..Default::default()
+        };
+        assert!(validate_edit_span(&insertion, content).is_ok());
+
+        let reversed = Edit {
+            byte_start: 3,
+            byte_end: 2,
+            replacement: String::new(),
+            expected_checksum: String::new(),
+            ..Default::default()
+        };
+        match validate_edit_span(&reversed, content) {
+            Err(EditError::InvalidSpan {
+                byte_start: 3,
+                byte_end: 2,
+            }) => {}
+            other => panic!("Expected EditError::InvalidSpan, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_apply_edits_insertions_same_offset_keep_request_order() {
+        let content = "fn main() {}";
+        let checksum = compute_checksum(content);
+
+        let edits = vec![
+            Edit {
+                byte_start: 0,
+                byte_end: 0,
+                replacement: "use a;\n".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+            Edit {
+                byte_start: 0,
+                byte_end: 0,
+                replacement: "use b;\n".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+        ];
+
+        let result = apply_edits(content, &checksum, &edits).unwrap();
+
+        assert_eq!(result.applied_count, 2);
+        assert_eq!(result.total_byte_shift, 14);
+        assert_eq!(result.final_content, "use a;\nuse b;\nfn main() {}");
+    }
+
+    #[test]
+    fn test_apply_edits_insertion_next_to_replacement() {
+        let content = "let x = 1;";
+        let checksum = compute_checksum(content);
+
+        // Replace "1" (8..9), insert before it and after it
+        let edits = vec![
+            Edit {
+                byte_start: 9,
+                byte_end: 9,
+                replacement: " + 3".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+            Edit {
+                byte_start: 8,
+                byte_end: 9,
+                replacement: "2".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+            Edit {
+                byte_start: 8,
+                byte_end: 8,
+                replacement: "0 + ".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+        ];
+
+        let result = apply_edits(content, &checksum, &edits).unwrap();
+
+        assert_eq!(result.applied_count, 3);
+        assert_eq!(result.final_content, "let x = 0 + 2 + 3;");
+    }
+
+    #[test]
+    fn test_apply_edits_rejects_overlap() {
+        let content = "The quick brown fox";
+        let checksum = compute_checksum(content);
+
+        let edits = vec![
+            Edit {
+                byte_start: 10,
+                byte_end: 15,
+                replacement: "red".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+            Edit {
+                byte_start: 4,
+                byte_end: 12,
+                replacement: "slow".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+            // Insertion strictly inside the first edit
+            Edit {
+                byte_start: 12,
+                byte_end: 12,
+                replacement: "x".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+        ];
+
+        match apply_edits(content, &checksum, &edits) {
+            Err(EditError::OverlappingEdits {
+                first_index,
+                first_span,
+                second_index,
+                second_span,
+            }) => {
+                assert_eq!(first_index, 1);
+                assert_eq!(
+                    first_span,
+                    Span {
+                        byte_start: 4,
+                        byte_end: 12
+                    }
+                );
+                assert_eq!(second_index, 0);
+                assert_eq!(
+                    second_span,
+                    Span {
+                        byte_start: 10,
+                        byte_end: 15
+                    }
+                );
+            }
+            other => panic!("Expected EditError::OverlappingEdits, got {:?}", other),
+        }
+
+        // Insertion inside a replacement is an overlap as well
+        match check_overlaps(
+            &edits[0..1]
+                .iter()
+                .chain(&edits[2..])
+                .cloned()
+                .collect::<Vec<_>>(),
+            &[],
+        ) {
+            Err(EditError::OverlappingEdits {
+                first_index: 0,
+                second_index: 1,
+                ..
+            }) => {}
+            other => panic!("Expected EditError::OverlappingEdits, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_apply_edits_merge_duplicates() {
+        let content = "Hello, world!";
+        let checksum = compute_checksum(content);
+
+        let edit = Edit {
+            byte_start: 7,
+            byte_end: 12,
+            replacement: "Rust".to_string(),
+            expected_checksum: checksum.clone(),
+            ..Default::default()
+        };
+        let edits = vec![edit.clone(), edit];
+
+        // Without merging, identical edits overlap
+        assert!(matches!(
+            apply_edits(content, &checksum, &edits),
+            Err(EditError::OverlappingEdits { .. })
+        ));
+
+        // With merging, the second one is skipped as a duplicate
+        let options = ApplyOptions::default().with_merge_duplicates(true);
+        let result = apply_edits_with_options(content, &checksum, &edits, &options).unwrap();
+
+        assert_eq!(result.applied_count, 1);
+        assert_eq!(result.skipped_count, 1);
+        assert!(result.edits.contains(&PerEditResult::Skipped {
+            byte_offset: 7,
+            reason: "duplicate".to_string(),
+        }));
+        assert_eq!(result.final_content, "Hello, Rust!");
+    }
+
+    #[test]
+    fn test_apply_edits_transactional_rollback() {
+        let content = "Hello, world!";
+        let checksum = compute_checksum(content);
+
+        let edits = vec![
+            Edit {
+                byte_start: 0,
+                byte_end: 5,
+                replacement: "Howdy".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+            // Out of bounds, applied first (highest offset)
+            Edit {
+                byte_start: 20,
+                byte_end: 25,
+                replacement: "x".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+        ];
+
+        // Default mode stops at the first error
+        let result = apply_edits(content, &checksum, &edits).unwrap();
+        assert_eq!(result.error_count, 1);
+        assert_eq!(result.applied_count, 0);
+        assert_eq!(result.edits.len(), 1);
+        assert_eq!(result.edit_indices, vec![1]);
+
+        // Transactional mode reports every edit and leaves the content untouched
+        let options = ApplyOptions::default().with_transactional(true);
+        let result = apply_edits_with_options(content, &checksum, &edits, &options).unwrap();
+
+        assert_eq!(result.error_count, 1);
+        assert_eq!(result.rolled_back_count, 1);
+        assert_eq!(result.applied_count, 0);
+        assert_eq!(result.total_byte_shift, 0);
+        assert_eq!(result.final_content, content);
+        assert_eq!(result.final_checksum, checksum);
+        assert!(matches!(
+            result.edits[0],
+            PerEditResult::Error {
+                byte_offset: 20,
+                ..
+            }
+        ));
+        assert_eq!(
+            result.edits[1],
+            PerEditResult::RolledBack { byte_offset: 0 }
+        );
+        assert_eq!(result.edit_indices, vec![1, 0]);
+    }
+
+    #[test]
+    fn test_apply_edits_transactional_success() {
+        let content = "Hello, world!";
+        let checksum = compute_checksum(content);
+
+        let edits = vec![Edit {
+            byte_start: 7,
+            byte_end: 12,
+            replacement: "Rust".to_string(),
+            expected_checksum: checksum.clone(),
+            ..Default::default()
+        }];
+
+        let options = ApplyOptions::default().with_transactional(true);
+        let result = apply_edits_with_options(content, &checksum, &edits, &options).unwrap();
+
+        assert!(result.is_complete_success());
+        assert_eq!(result.rolled_back_count, 0);
+        assert_eq!(result.final_content, "Hello, Rust!");
+    }
+
+    #[test]
+    fn test_apply_edits_per_edit_checksum() {
+        let content = "Hello, world!";
+        let checksum = compute_checksum(content);
+        let edit = |expected_checksum: &str| Edit {
+            byte_start: 7,
+            byte_end: 12,
+            replacement: "Rust".to_string(),
+            expected_checksum: expected_checksum.to_string(),
+            ..Default::default()
+        };
+
+        // Empty, "any" and the batch's own checksum all pass
+        for expected in ["", ANY_CHECKSUM, checksum.as_str()] {
+            assert!(
+                apply_edits(content, &checksum, &[edit(expected)])
+                    .unwrap()
+                    .is_complete_success()
+            );
+        }
+
+        // An edit built against another version of the file fails
+        let result = apply_edits(content, &checksum, &[edit("stale")]).unwrap();
+        assert_eq!(result.error_count, 1);
+        assert_eq!(result.final_content, content);
+
+        // With an "any" batch the edit is held to the content's checksum
+        let pinned = Edit {
+            expected_text: Some("world".to_string()),
+            ..edit(&checksum)
+        };
+        assert!(
+            apply_edits(content, ANY_CHECKSUM, &[pinned])
+                .unwrap()
+                .is_complete_success()
+        );
+        let stale = Edit {
+            expected_text: Some("world".to_string()),
+            ..edit("stale")
+        };
+        assert_eq!(
+            apply_edits(content, ANY_CHECKSUM, &[stale])
+                .unwrap()
+                .error_count,
+            1
+        );
+    }
+
+    #[test]
+    fn test_apply_edits_validate_syntax() {
+        let content = "fn a() {\n    1\n}\n\nfn b() {\n    2\n}\n";
+        let checksum = compute_checksum(content);
+        let options = ApplyOptions::default().with_validate_syntax(Some(Language::Rust));
+
+        // Dropping the closing brace of `a` is rejected, with the error located
+        let drop_brace = vec![Edit {
+            byte_start: 15,
+            byte_end: 17,
+            replacement: String::new(),
+            expected_checksum: checksum.clone(),
+            ..Default::default()
+        }];
+        match apply_edits_with_options(content, &checksum, &drop_brace, &options) {
+            Err(EditError::SyntaxRegression { errors }) => {
+                // Tree-sitter gives up on `a` as a whole
+                assert_eq!(errors.len(), 1);
+                assert!(
+                    errors[0].message.starts_with("unexpected \"fn a() {"),
+                    "{}",
+                    errors[0].message
+                );
+                assert_eq!(
+                    errors[0].start,
+                    crate::position::Position { line: 1, column: 1 }
+                );
+            }
+            other => panic!("Expected EditError::SyntaxRegression, got {:?}", other),
+        }
+        // Without validation the same edit goes through
+        assert!(
+            apply_edits(content, &checksum, &drop_brace)
+                .unwrap()
+                .is_complete_success()
+        );
+
+        // Errors that were already there (and moved) are not held against the batch
+        let broken = "fn a() {\n    1\n\nfn b() {\n    2\n}\n";
+        let broken_checksum = compute_checksum(broken);
+        let unrelated = Edit {
+            byte_start: 0,
+            byte_end: 0,
+            replacement: "// a comment\n".to_string(),
+            expected_checksum: broken_checksum.clone(),
+            ..Default::default()
+        };
+        let result = apply_edits_with_options(
+            broken,
+            &broken_checksum,
+            std::slice::from_ref(&unrelated),
+            &options,
+        )
+        .unwrap();
+        assert!(result.is_complete_success());
+
+        // A language without a grammar can't be validated
+        let markdown = ApplyOptions::default().with_validate_syntax(Some(Language::Markdown));
+        assert!(matches!(
+            apply_edits_with_options(broken, &broken_checksum, &[unrelated], &markdown),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+    }
+
+    #[test]
+    fn test_apply_edits_intermediate_checksums() {
+        let content = "The quick brown fox jumps over the lazy dog.";
+        let checksum = compute_checksum(content);
+
+        let edits = vec![
+            Edit {
+                byte_start: 4,
+                byte_end: 9,
+                replacement: "slow".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+            Edit {
+                byte_start: 35,
+                byte_end: 39,
+                replacement: "active".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+        ];
+
+        // Off by default
+        let result = apply_edits(content, &checksum, &edits).unwrap();
+        assert!(result.edits.iter().all(|r| matches!(
+            r,
+            PerEditResult::Applied {
+                new_checksum: None,
+                ..
+            }
+        )));
+
+        let options = ApplyOptions::default().with_intermediate_checksums(true);
+        let result = apply_edits_with_options(content, &checksum, &edits, &options).unwrap();
+
+        // Highest offset is applied first
+        let after_first = compute_checksum("The quick brown fox jumps over the active dog.");
+        match &result.edits[0] {
+            PerEditResult::Applied {
+                byte_offset: 35,
+                new_checksum,
+                ..
+            } => {
+                assert_eq!(new_checksum.as_deref(), Some(after_first.as_str()));
+            }
+            other => panic!("Expected PerEditResult::Applied, got {:?}", other),
+        }
+        match &result.edits[1] {
+            PerEditResult::Applied {
+                byte_offset: 4,
+                new_checksum,
+                ..
+            } => {
+                assert_eq!(
+                    new_checksum.as_deref(),
+                    Some(result.final_checksum.as_str())
+                );
+            }
+            other => panic!("Expected PerEditResult::Applied, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_apply_edits_large_batch() {
+        let line = "let value = 0;\n";
+        let content = line.repeat(500);
+        let checksum = compute_checksum(&content);
+
+        // Replace every "0" with the line number, in shuffled request order
+        let mut edits: Vec<Edit> = (0..500)
+            .map(|i| Edit {
+                byte_start: i * line.len() + 12,
+                byte_end: i * line.len() + 13,
+                replacement: i.to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            })
+            .collect();
+        edits.reverse();
+        edits.swap(3, 400);
+
+        let result = apply_edits(&content, &checksum, &edits).unwrap();
+
+        let expected: String = (0..500).map(|i| format!("let value = {};\n", i)).collect();
+        assert_eq!(result.applied_count, 500);
+        assert_eq!(result.final_content, expected);
+        assert_eq!(result.final_checksum, compute_checksum(&expected));
+        assert_eq!(
+            result.total_byte_shift,
+            expected.len() as i64 - content.len() as i64
+        );
+    }
+
+    #[test]
+    fn test_validate_edit_span_not_char_boundary() {
+        // "é" is two bytes (1..3)
+        let content = "héllo";
+        let edit = Edit {
+            byte_start: 2,
+            byte_end: 4,
+            replacement: "e".to_string(),
+            expected_checksum: String::new(),
+            ..Default::default()
+        };
+
+        match validate_edit_span(&edit, content) {
+            Err(EditError::NotCharBoundary {
+                byte_offset,
+                prev_boundary,
+                next_boundary,
+            }) => {
+                assert_eq!(byte_offset, 2);
+                assert_eq!(prev_boundary, 1);
+                assert_eq!(next_boundary, 3);
+            }
+            other => panic!("Expected EditError::NotCharBoundary, got {:?}", other),
+        }
+
+        // The batch reports the error instead of corrupting the text
+        let checksum = compute_checksum(content);
+        let result = apply_edits(content, &checksum, &[edit]).unwrap();
+        assert_eq!(result.error_count, 1);
+        assert_eq!(result.final_content, content);
+    }
+
+    #[test]
+    fn test_apply_edits_snap_to_char_boundary() {
+        // "→" is three bytes (2..5)
+        let content = "a →b";
+        let checksum = compute_checksum(content);
+        let edits = vec![Edit {
+            byte_start: 3,
+            byte_end: 4,
+            replacement: "->".to_string(),
+            expected_checksum: checksum.clone(),
+            ..Default::default()
+        }];
+
+        let options = ApplyOptions::default().with_snap_to_char_boundary(true);
+        let result = apply_edits_with_options(content, &checksum, &edits, &options).unwrap();
+
+        assert_eq!(result.applied_count, 1);
+        assert_eq!(result.final_content, "a ->b");
+        assert_eq!(
+            result.edits[0],
+            PerEditResult::Applied {
+                byte_offset: 2,
+                new_checksum: None,
+                byte_shift: -1,
+                relocation: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_apply_edits_expected_text() {
+        let content = "Hello, world!";
+        let checksum = compute_checksum(content);
+
+        let matching = Edit {
+            byte_start: 7,
+            byte_end: 12,
+            replacement: "Rust".to_string(),
+            expected_checksum: checksum.clone(),
+            expected_text: Some("world".to_string()),
+            ..Default::default()
+        };
+        let result = apply_edits(content, &checksum, std::slice::from_ref(&matching)).unwrap();
+        assert_eq!(result.final_content, "Hello, Rust!");
+
+        let stale = Edit {
+            expected_text: Some("earth".to_string()),
+            ..matching
+        };
+        let result = apply_edits(content, &checksum, &[stale]).unwrap();
+        assert_eq!(result.error_count, 1);
+        assert_eq!(result.final_content, content);
+        match &result.edits[0] {
+            PerEditResult::Error { error, .. } => {
+                assert!(error.contains("text mismatch"), "{}", error)
+            }
+            other => panic!("Expected PerEditResult::Error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_verify_span_checksum() {
+        let content = "Hello, world!";
+        let mut edit = Edit {
+            byte_start: 0,
+            byte_end: 5,
+            replacement: "Howdy".to_string(),
+            expected_span_checksum: Some(compute_checksum("Hello")),
+            ..Default::default()
+        };
+        assert!(verify_span(&edit, content).is_ok());
+
+        edit.expected_span_checksum = Some(compute_checksum("Howdy"));
+        match verify_span(&edit, content) {
+            Err(EditError::SpanChecksumMismatch { actual, .. }) => {
+                assert_eq!(actual, compute_checksum("Hello"))
+            }
+            other => panic!("Expected EditError::SpanChecksumMismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_verify_span_context() {
+        let content = "Hello, world!";
+        // An insertion pinned by the text on both sides
+        let mut edit = Edit {
+            byte_start: 5,
+            byte_end: 5,
+            replacement: " there".to_string(),
+            context_before: Some("Hello".to_string()),
+            context_after: Some(", world!".to_string()),
+            ..Default::default()
+        };
+        assert!(edit.has_span_precondition());
+        assert!(verify_span(&edit, content).is_ok());
+
+        edit.context_after = Some(", Rust!".to_string());
+        match verify_span(&edit, content) {
+            Err(EditError::SpanTextMismatch {
+                byte_start,
+                byte_end,
+                actual,
+                ..
+            }) => {
+                assert_eq!((byte_start, byte_end, actual.as_str()), (5, 12, ", world"));
+            }
+            other => panic!("Expected EditError::SpanTextMismatch, got {:?}", other),
+        }
+
+        // Context reaching past the start of the content
+        edit.context_after = None;
+        edit.context_before = Some("Oh, Hello".to_string());
+        assert!(matches!(
+            verify_span(&edit, content),
+            Err(EditError::SpanTextMismatch { byte_start: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_apply_edits_any_checksum_requires_span_precondition() {
+        let content = "Hello, world!";
+
+        let edits = vec![
+            Edit {
+                byte_start: 7,
+                byte_end: 12,
+                replacement: "Rust".to_string(),
+                expected_checksum: ANY_CHECKSUM.to_string(),
+                expected_text: Some("world".to_string()),
+                ..Default::default()
+            },
+            Edit {
+                byte_start: 0,
+                byte_end: 5,
+                replacement: "Howdy".to_string(),
+                expected_checksum: ANY_CHECKSUM.to_string(),
+                ..Default::default()
+            },
+        ];
+
+        // Span-verified edit goes through without the file checksum
+        let result = apply_edits(content, ANY_CHECKSUM, &edits[..1]).unwrap();
+        assert_eq!(result.final_content, "Hello, Rust!");
+        assert_eq!(result.final_checksum, compute_checksum("Hello, Rust!"));
+
+        // Unverified edit is rejected
+        let result = apply_edits(content, ANY_CHECKSUM, &edits).unwrap();
+        assert_eq!(result.applied_count, 1);
+        assert_eq!(result.error_count, 1);
+        match &result.edits[1] {
+            PerEditResult::Error {
+                byte_offset: 0,
+                error,
+                ..
+            } => assert!(error.contains("\"any\""), "{}", error),
+            other => panic!("Expected PerEditResult::Error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_apply_edits_relocate_drifted_edit() {
+        // Two lines were added above the target since the offsets were computed
+        let content = "// one\n// two\nfn main() {\n    old();\n}\n";
+        let stale_checksum = compute_checksum("fn main() {\n    old();\n}\n");
+
+        let edits = vec![Edit {
+            byte_start: 16,
+            byte_end: 19,
+            replacement: "new".to_string(),
+            expected_checksum: stale_checksum.clone(),
+            expected_text: Some("old".to_string()),
+            context_after: Some("();".to_string()),
+            ..Default::default()
+        }];
+
+        // Without relocation the stale checksum fails the batch
+        assert!(matches!(
+            apply_edits(content, &stale_checksum, &edits),
+            Err(EditError::ChecksumMismatch { .. })
+        ));
+
+        let options = ApplyOptions::default().with_relocate(true, None);
+        let result = apply_edits_with_options(content, &stale_checksum, &edits, &options).unwrap();
+
+        assert_eq!(
+            result.final_content,
+            "// one\n// two\nfn main() {\n    new();\n}\n"
+        );
+        assert_eq!(
+            result.edits[0],
+            PerEditResult::Applied {
+                byte_offset: 16,
+                new_checksum: None,
+                byte_shift: 0,
+                relocation: Some(Relocation {
+                    span: Span {
+                        byte_start: 30,
+                        byte_end: 33
+                    },
+                    drift: 14,
+                }),
+            }
+        );
+
+        // A tight window rejects the move and lists the match it found
+        let options = ApplyOptions::default().with_relocate(true, Some(8));
+        let result = apply_edits_with_options(content, &stale_checksum, &edits, &options).unwrap();
+        assert_eq!(result.error_count, 1);
+        match &result.edits[0] {
+            PerEditResult::Error { candidates, .. } => {
+                assert_eq!(
+                    candidates,
+                    &vec![Span {
+                        byte_start: 30,
+                        byte_end: 33
+                    }]
+                );
+            }
+            other => panic!("Expected PerEditResult::Error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_relocate_edit_ambiguous() {
+        let content = "a = x;\nb = x;\n";
+        let edit = Edit {
+            byte_start: 0,
+            byte_end: 1,
+            replacement: "y".to_string(),
+            expected_text: Some("x".to_string()),
+            context_after: Some(";".to_string()),
+            ..Default::default()
+        };
+
+        match relocate_edit(&edit, content, None) {
+            Err(EditError::AnchorAmbiguous { candidates, .. }) => {
+                assert_eq!(
+                    candidates,
+                    vec![
+                        Span {
+                            byte_start: 4,
+                            byte_end: 5
+                        },
+                        Span {
+                            byte_start: 11,
+                            byte_end: 12
+                        },
+                    ]
+                );
+            }
+            other => panic!("Expected EditError::AnchorAmbiguous, got {:?}", other),
+        }
+
+        // An anchor already in place is never moved
+        let in_place = Edit {
+            byte_start: 4,
+            byte_end: 5,
+            ..edit
+        };
+        assert_eq!(
+            relocate_edit(&in_place, content, None).unwrap().byte_start,
+            4
+        );
+    }
+
+    #[test]
+    fn test_apply_edits_undo_restores_original() {
+        let content = "fn a() {}\nfn b() {}\nfn c() {}\n";
+        let checksum = compute_checksum(content);
+
+        let edits = vec![
+            // Rename a -> alpha
+            Edit {
+                byte_start: 3,
+                byte_end: 4,
+                replacement: "alpha".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+            // Delete the b line
+            Edit {
+                byte_start: 10,
+                byte_end: 20,
+                replacement: String::new(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+            // Insert at the end
+            Edit {
+                byte_start: 30,
+                byte_end: 30,
+                replacement: "fn d() {}\n".to_string(),
+                expected_checksum: checksum.clone(),
+                ..Default::default()
+            },
+        ];
+
+        let result = apply_edits(content, &checksum, &edits).unwrap();
+        assert_eq!(
+            result.final_content,
+            "fn alpha() {}\nfn c() {}\nfn d() {}\n"
+        );
+        assert_eq!(result.undo.len(), 3);
+        assert!(
+            result
+                .undo
+                .iter()
+                .all(|e| e.expected_checksum == result.final_checksum)
+        );
+
+        let undone =
+            apply_edits(&result.final_content, &result.final_checksum, &result.undo).unwrap();
+        assert!(undone.is_complete_success());
+        assert_eq!(undone.final_content, content);
+        assert_eq!(undone.final_checksum, checksum);
+
+        // Undoing the undo redoes the batch
+        let redone =
+            apply_edits(&undone.final_content, &undone.final_checksum, &undone.undo).unwrap();
+        assert_eq!(redone.final_content, result.final_content);
     }
 }
@@ -1,7 +1,11 @@
-use std::path::Path;
-use std::io;
-use std::fs;
 use blake3;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::language::{Language, detect_language_from_content};
+use crate::position::{LineEnding, LineIndex};
 
 /// Content of a file read into memory
 #[derive(Debug, Clone)]
@@ -14,6 +18,12 @@ pub struct FileContent {
     pub len: usize,
     /// BLAKE3 hash of the content (hex-encoded)
     pub checksum: String,
+    /// Line starts of the content, for offset <-> position conversion
+    pub line_index: LineIndex,
+    /// Line break style (`None` if the content has no line breaks)
+    pub line_ending: Option<LineEnding>,
+    /// Language detected from the file name and content
+    pub language: Language,
 }
 
 /// Error types for file operations
@@ -22,6 +32,12 @@ pub enum FileError {
     NotFound(String),
     IoError(String),
     InvalidUtf8(String),
+    /// File content after write-back does not match the expected checksum
+    ChecksumMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
 }
 
 impl std::fmt::Display for FileError {
@@ -30,6 +46,15 @@ impl std::fmt::Display for FileError {
             FileError::NotFound(p) => write!(f, "File not found: {}", p),
             FileError::IoError(e) => write!(f, "I/O error: {}", e),
             FileError::InvalidUtf8(p) => write!(f, "Invalid UTF-8 in file: {}", p),
+            FileError::ChecksumMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Checksum mismatch after writing {}: expected {}, got {}",
+                path, expected, actual
+            ),
         }
     }
 }
@@ -71,14 +96,103 @@ pub fn read_file<P: AsRef<Path>>(path: P) -> Result<FileContent, FileError> {
     let checksum = blake3::hash(content.as_bytes());
     let checksum_hex = checksum.to_hex().to_string();
 
+    let line_index = LineIndex::new(&content);
+    let line_ending = LineEnding::detect(&content);
+    let language = detect_language_from_content(path_ref, &content).language;
+
     Ok(FileContent {
         path: path_ref.display().to_string(),
         content,
         len,
         checksum: checksum_hex,
+        line_index,
+        line_ending,
+        language,
     })
 }
 
+/// Atomically replace a file's content and verify the result
+///
+/// The content is written to a temporary file in the same directory, synced
+/// to disk and renamed over the target, so readers observe either the old or
+/// the new content but never a partially written file. The original file's
+/// permissions are preserved. After the rename the file is read back and its
+/// checksum compared against `expected_checksum`.
+///
+/// # Arguments
+/// * `path` - Path to the file to replace
+/// * `content` - New file content
+/// * `expected_checksum` - BLAKE3 checksum (hex-encoded) the written file must have
+///
+/// # Returns
+/// * `Ok(FileContent)` - The file as read back from disk
+/// * `Err(FileError)` - I/O error, or the re-read content has a different checksum
+pub fn write_file_atomic<P: AsRef<Path>>(
+    path: P,
+    content: &str,
+    expected_checksum: &str,
+) -> Result<FileContent, FileError> {
+    let path_ref = path.as_ref();
+    let temp_path = temp_path_for(path_ref);
+
+    if let Err(e) = write_temp_and_rename(path_ref, &temp_path, content) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    // Re-read and verify what actually landed on disk
+    let written = read_file(path_ref)?;
+    if written.checksum != expected_checksum {
+        return Err(FileError::ChecksumMismatch {
+            path: path_ref.display().to_string(),
+            expected: expected_checksum.to_string(),
+            actual: written.checksum,
+        });
+    }
+
+    Ok(written)
+}
+
+/// Build a unique temporary path next to `path` (same directory, so rename is atomic)
+fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let temp_name = format!(".{}.{}.tmp", file_name, Uuid::new_v4());
+
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join(temp_name),
+        _ => PathBuf::from(temp_name),
+    }
+}
+
+/// Write `content` to `temp_path`, copy permissions from `path` and rename over it
+fn write_temp_and_rename(path: &Path, temp_path: &Path, content: &str) -> io::Result<()> {
+    let mut temp_file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(temp_path)?;
+    temp_file.write_all(content.as_bytes())?;
+    temp_file.sync_all()?;
+    drop(temp_file);
+
+    // Keep the original permissions (the temp file gets the default umask)
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(temp_path, metadata.permissions())?;
+    }
+
+    fs::rename(temp_path, path)?;
+
+    // Persist the rename itself
+    #[cfg(unix)]
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::File::open(parent)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,6 +227,33 @@ mod tests {
         fs::remove_file(&file_path).unwrap();
     }
 
+    #[test]
+    fn test_read_file_mixed_line_endings() {
+        let temp_dir = std::env::temp_dir();
+        let file_path = temp_dir.join("test_mixed_line_endings.txt");
+        let content = "first\r\nsecond\nthird\rfourth";
+
+        fs::write(&file_path, content.as_bytes()).unwrap();
+        let file_content = read_file(&file_path).unwrap();
+
+        assert_eq!(file_content.line_ending, Some(LineEnding::Mixed));
+        assert_eq!(file_content.line_index.line_count(), 4);
+        let fourth = file_content.line_index.byte_to_position(
+            &file_content.content,
+            content.find("fourth").unwrap(),
+            crate::position::PositionEncoding::Utf8,
+        );
+        assert_eq!((fourth.line, fourth.column), (4, 1));
+
+        fs::write(&file_path, "a\r\nb\r\n".as_bytes()).unwrap();
+        assert_eq!(
+            read_file(&file_path).unwrap().line_ending,
+            Some(LineEnding::CrLf)
+        );
+
+        fs::remove_file(&file_path).unwrap();
+    }
+
     #[test]
     fn test_read_file_invalid_utf8() {
         // Create a temporary file with invalid UTF-8 content
@@ -153,4 +294,77 @@ mod tests {
             _ => panic!("Expected FileError::NotFound"),
         }
     }
+
+    #[test]
+    fn test_write_file_atomic() {
+        let temp_dir = std::env::temp_dir();
+        let file_path = temp_dir.join("test_write_file_atomic.txt");
+        fs::write(&file_path, "old content").unwrap();
+
+        let new_content = "new content\n";
+        let checksum = blake3::hash(new_content.as_bytes()).to_hex().to_string();
+
+        let result = write_file_atomic(&file_path, new_content, &checksum);
+
+        assert!(result.is_ok());
+        let written = result.unwrap();
+        assert_eq!(written.content, new_content);
+        assert_eq!(written.checksum, checksum);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), new_content);
+
+        // No temporary files should be left behind
+        let leftovers = fs::read_dir(&temp_dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                e.file_name()
+                    .to_string_lossy()
+                    .starts_with(".test_write_file_atomic.txt.")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        // Clean up
+        fs::remove_file(&file_path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_file_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let file_path = std::env::temp_dir().join("test_write_file_atomic_perms.sh");
+        fs::write(&file_path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&file_path, fs::Permissions::from_mode(0o750)).unwrap();
+
+        let new_content = "#!/bin/sh\necho hi\n";
+        let checksum = blake3::hash(new_content.as_bytes()).to_hex().to_string();
+        write_file_atomic(&file_path, new_content, &checksum).unwrap();
+
+        let mode = fs::metadata(&file_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+
+        // Clean up
+        fs::remove_file(&file_path).unwrap();
+    }
+
+    #[test]
+    fn test_write_file_atomic_checksum_mismatch() {
+        let file_path = std::env::temp_dir().join("test_write_file_atomic_mismatch.txt");
+
+        let result = write_file_atomic(&file_path, "content", "0000");
+
+        match result {
+            Err(FileError::ChecksumMismatch {
+                expected, actual, ..
+            }) => {
+                assert_eq!(expected, "0000");
+                assert_eq!(actual, blake3::hash(b"content").to_hex().to_string());
+            }
+            _ => panic!("Expected FileError::ChecksumMismatch"),
+        }
+
+        // Clean up
+        fs::remove_file(&file_path).unwrap();
+    }
 }
@@ -0,0 +1,395 @@
+use serde::{Deserialize, Serialize};
+
+use crate::edit::{Edit, EditError};
+use crate::position::{LineIndex, Span, with_line_break};
+
+/// Context lines `patch` may ignore on each side of a hunk by default
+pub const DEFAULT_MAX_FUZZ: usize = 2;
+
+/// A unified-diff hunk, split into the parts an edit needs
+///
+/// Leading and trailing context lines anchor the hunk; everything between
+/// them (including inner context lines) is the old text that gets replaced
+/// by the new text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hunk {
+    /// Line (1-indexed) where the hunk is expected to start, if known
+    pub line: Option<usize>,
+    /// Context lines before the change
+    pub before_context: String,
+    /// Text that is replaced
+    pub old_text: String,
+    /// Text that replaces `old_text`
+    pub new_text: String,
+    /// Context lines after the change
+    pub after_context: String,
+}
+
+/// Where a hunk was found in the content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HunkMatch {
+    /// Span of the hunk's old text
+    pub span: Span,
+    /// Lines between the hinted and the actual start of the hunk (0 without a hint)
+    pub line_offset: i64,
+    /// Context lines ignored at each end of the hunk to find it
+    pub fuzz: usize,
+}
+
+impl Hunk {
+    /// Parse the body of a unified-diff hunk
+    ///
+    /// An optional `@@ -l,s +l,s @@` header provides the line hint. Body
+    /// lines start with `' '` (context), `'-'` (old) or `'+'` (new); an empty
+    /// line is an empty context line, and `\ No newline at end of file`
+    /// removes the line break of the line before it.
+    ///
+    /// # Returns
+    /// * `Ok(Hunk)` - The parsed hunk
+    /// * `Err(EditError::InvalidEditSpec)` - Malformed header or body line
+    pub fn parse(text: &str) -> Result<Self, EditError> {
+        let mut line = None;
+        let mut lines: Vec<(char, String)> = Vec::new();
+
+        for raw in text.split_inclusive('\n') {
+            if raw.starts_with("@@") {
+                if line.is_some() || !lines.is_empty() {
+                    return Err(EditError::InvalidEditSpec(
+                        "hunk has more than one @@ header".to_string(),
+                    ));
+                }
+                line = Some(parse_header(raw)?);
+                continue;
+            }
+            let body = if raw.ends_with('\n') {
+                raw.to_string()
+            } else {
+                format!("{}\n", raw)
+            };
+            match body.chars().next() {
+                Some(tag @ (' ' | '-' | '+')) => lines.push((tag, body[1..].to_string())),
+                Some('\n' | '\r') => lines.push((' ', body)),
+                Some('\\') => match lines.last_mut() {
+                    Some((_, previous)) => strip_line_break(previous),
+                    None => {
+                        return Err(EditError::InvalidEditSpec(
+                            "\"\\ No newline at end of file\" without a line before it".to_string(),
+                        ));
+                    }
+                },
+                _ => {
+                    return Err(EditError::InvalidEditSpec(format!(
+                        "invalid hunk line {:?}: expected ' ', '-' or '+'",
+                        raw.trim_end()
+                    )));
+                }
+            }
+        }
+
+        let leading = lines.iter().take_while(|(tag, _)| *tag == ' ').count();
+        let trailing = lines[leading..]
+            .iter()
+            .rev()
+            .take_while(|(tag, _)| *tag == ' ')
+            .count();
+        let middle = &lines[leading..lines.len() - trailing];
+
+        Ok(Self {
+            line,
+            before_context: lines[..leading]
+                .iter()
+                .map(|(_, text)| text.as_str())
+                .collect(),
+            old_text: middle
+                .iter()
+                .filter(|(tag, _)| *tag != '+')
+                .map(|(_, text)| text.as_str())
+                .collect(),
+            new_text: middle
+                .iter()
+                .filter(|(tag, _)| *tag != '-')
+                .map(|(_, text)| text.as_str())
+                .collect(),
+            after_context: lines[lines.len() - trailing..]
+                .iter()
+                .map(|(_, text)| text.as_str())
+                .collect(),
+        })
+    }
+
+    /// The hunk with every line break converted to `line_break`
+    pub fn with_line_break(&self, line_break: &str) -> Self {
+        Self {
+            line: self.line,
+            before_context: with_line_break(&self.before_context, line_break),
+            old_text: with_line_break(&self.old_text, line_break),
+            new_text: with_line_break(&self.new_text, line_break),
+            after_context: with_line_break(&self.after_context, line_break),
+        }
+    }
+
+    /// Find the hunk in `content`, the way `patch` does
+    ///
+    /// The hunk (context and old text) must start at the beginning of a
+    /// line. With a line hint, the match closest to it wins and its distance
+    /// is reported as the line offset; without one, the match must be unique.
+    /// If there is no match, up to `max_fuzz` context lines are dropped from
+    /// the outer ends of the hunk, one more on each side per attempt.
+    ///
+    /// # Arguments
+    /// * `content` - The content to search
+    /// * `line_index` - Line index of `content`
+    /// * `max_fuzz` - Maximum number of context lines to ignore on each side
+    ///
+    /// # Returns
+    /// * `Ok(HunkMatch)` - The span of the old text, with the offset and fuzz used
+    /// * `Err(EditError::HunkMismatch)` - No match, or several equally good ones (listed)
+    pub fn locate(
+        &self,
+        content: &str,
+        line_index: &LineIndex,
+        max_fuzz: usize,
+    ) -> Result<HunkMatch, EditError> {
+        let before: Vec<&str> = self.before_context.split_inclusive('\n').collect();
+        let after: Vec<&str> = self.after_context.split_inclusive('\n').collect();
+
+        for fuzz in 0..=max_fuzz.min(before.len().max(after.len())) {
+            let skipped_before = fuzz.min(before.len());
+            let before_text: String = before[skipped_before..].concat();
+            let after_text: String = after[..after.len() - fuzz.min(after.len())].concat();
+            let anchor = format!("{}{}{}", before_text, self.old_text, after_text);
+
+            let starts = line_start_matches(content, line_index, &anchor);
+            if starts.is_empty() {
+                continue;
+            }
+
+            // Line where the complete hunk would start, for each match
+            let hunk_line = |start: usize| line_index.line_of(start) as i64 - skipped_before as i64;
+            let best = match self.line {
+                Some(hint) => {
+                    let distance = |start: usize| (hunk_line(start) - hint as i64).abs();
+                    let nearest = starts
+                        .iter()
+                        .map(|&start| distance(start))
+                        .min()
+                        .unwrap_or(0);
+                    starts
+                        .iter()
+                        .copied()
+                        .filter(|&start| distance(start) == nearest)
+                        .collect::<Vec<_>>()
+                }
+                None => starts.clone(),
+            };
+            if best.len() > 1 {
+                return Err(self.mismatch(max_fuzz, best, before_text.len()));
+            }
+
+            let byte_start = best[0] + before_text.len();
+            return Ok(HunkMatch {
+                span: Span {
+                    byte_start,
+                    byte_end: byte_start + self.old_text.len(),
+                },
+                line_offset: self.line.map_or(0, |hint| hunk_line(best[0]) - hint as i64),
+                fuzz,
+            });
+        }
+
+        Err(self.mismatch(max_fuzz, Vec::new(), 0))
+    }
+
+    /// The edit that applies this hunk at `hunk_match`, checking the old text
+    pub fn to_edit(&self, hunk_match: &HunkMatch) -> Edit {
+        Edit {
+            byte_start: hunk_match.span.byte_start,
+            byte_end: hunk_match.span.byte_end,
+            replacement: self.new_text.clone(),
+            expected_text: Some(self.old_text.clone()),
+            ..Default::default()
+        }
+    }
+
+    fn mismatch(&self, max_fuzz: usize, starts: Vec<usize>, before_len: usize) -> EditError {
+        EditError::HunkMismatch {
+            line: self.line,
+            max_fuzz,
+            candidates: starts
+                .into_iter()
+                .map(|start| Span {
+                    byte_start: start + before_len,
+                    byte_end: start + before_len + self.old_text.len(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Old-side start line from a `@@ -l,s +l,s @@` header
+fn parse_header(header: &str) -> Result<usize, EditError> {
+    let invalid =
+        || EditError::InvalidEditSpec(format!("invalid hunk header {:?}", header.trim_end()));
+    let old_range = header
+        .trim_start_matches('@')
+        .split_whitespace()
+        .next()
+        .and_then(|range| range.strip_prefix('-'))
+        .ok_or_else(invalid)?;
+    let (start, count) = old_range.split_once(',').unwrap_or((old_range, "1"));
+    let start = start.parse::<usize>().map_err(|_| invalid())?;
+    // A hunk without old lines (an addition with no context, e.g. from
+    // `diff -U0`, or to an empty file) inserts after line `start`
+    match count.parse::<usize>().map_err(|_| invalid())? {
+        0 => Ok(start + 1),
+        _ => Ok(start),
+    }
+}
+
+/// Remove one trailing line break from `text`
+fn strip_line_break(text: &mut String) {
+    if text.ends_with('\n') {
+        text.pop();
+    }
+    if text.ends_with('\r') {
+        text.pop();
+    }
+}
+
+/// Start offsets of every (possibly overlapping) match of `anchor` that begins a line
+///
+/// An empty anchor matches every line start, leaving the choice to the line hint.
+fn line_start_matches(content: &str, line_index: &LineIndex, anchor: &str) -> Vec<usize> {
+    if anchor.is_empty() {
+        return (1..=line_index.line_count())
+            .filter_map(|line| line_index.line_span(line).map(|span| span.byte_start))
+            .collect();
+    }
+    let mut starts = Vec::new();
+    let mut from = 0;
+    while let Some(found) = content[from..].find(anchor) {
+        let start = from + found;
+        if line_index
+            .line_span(line_index.line_of(start))
+            .map(|span| span.byte_start)
+            == Some(start)
+        {
+            starts.push(start);
+        }
+        from = start + content[start..].chars().next().map_or(1, char::len_utf8);
+    }
+    starts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hunk() {
+        let hunk =
+            Hunk::parse("@@ -2,4 +2,4 @@ fn main() {\n a\n-b\n+B\n c\n-d\n+D\n e\n").unwrap();
+        assert_eq!(hunk.line, Some(2));
+        assert_eq!(hunk.before_context, "a\n");
+        assert_eq!(hunk.old_text, "b\nc\nd\n");
+        assert_eq!(hunk.new_text, "B\nc\nD\n");
+        assert_eq!(hunk.after_context, "e\n");
+
+        // No header, missing final line break, "\ No newline" marker
+        let hunk = Hunk::parse(" x\n-y\n\\ No newline at end of file\n+z").unwrap();
+        assert_eq!(hunk.line, None);
+        assert_eq!(hunk.old_text, "y");
+        assert_eq!(hunk.new_text, "z\n");
+
+        assert!(matches!(
+            Hunk::parse("@@ bogus @@\n a\n"),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+        assert!(matches!(
+            Hunk::parse(" a\n*b\n"),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+    }
+
+    #[test]
+    fn test_locate_with_offset() {
+        let content = "a\nb\nc\nx\na\nb\nc\n";
+        let index = LineIndex::new(content);
+        let hunk = Hunk::parse(" a\n-b\n+B\n c\n").unwrap();
+
+        // Without a hint the hunk is ambiguous
+        match hunk.locate(content, &index, 0) {
+            Err(EditError::HunkMismatch { candidates, .. }) => assert_eq!(candidates.len(), 2),
+            other => panic!("Expected EditError::HunkMismatch, got {:?}", other),
+        }
+
+        // The hint picks the closest match and reports the offset
+        let hunk = Hunk {
+            line: Some(4),
+            ..hunk
+        };
+        let found = hunk.locate(content, &index, 0).unwrap();
+        assert_eq!(
+            found.span,
+            Span {
+                byte_start: 10,
+                byte_end: 12
+            }
+        );
+        assert_eq!((found.line_offset, found.fuzz), (1, 0));
+    }
+
+    #[test]
+    fn test_locate_with_fuzz() {
+        let content = "one\ntwo\nthree\nfour\nfive\n";
+        let index = LineIndex::new(content);
+        let hunk = Hunk::parse("@@ -1,5 +1,5 @@\n ONE\n two\n-three\n+3\n four\n FIVE\n").unwrap();
+
+        assert!(matches!(
+            hunk.locate(content, &index, 0),
+            Err(EditError::HunkMismatch { .. })
+        ));
+        let found = hunk.locate(content, &index, DEFAULT_MAX_FUZZ).unwrap();
+        assert_eq!(
+            &content[found.span.byte_start..found.span.byte_end],
+            "three\n"
+        );
+        assert_eq!((found.line_offset, found.fuzz), (0, 1));
+
+        // Matches must start at the beginning of a line
+        let hunk = Hunk::parse("-wo\n+WO\n").unwrap();
+        assert!(hunk.locate(content, &index, 0).is_err());
+    }
+
+    #[test]
+    fn test_locate_addition_without_context() {
+        let content = "one\ntwo\n";
+        let index = LineIndex::new(content);
+
+        // `diff -U0` addition after line 1: the header's line places it
+        let hunk = Hunk::parse("@@ -1,0 +2,1 @@\n+1.5\n").unwrap();
+        assert_eq!(hunk.line, Some(2));
+        let found = hunk.locate(content, &index, DEFAULT_MAX_FUZZ).unwrap();
+        assert_eq!(
+            found.span,
+            Span {
+                byte_start: 4,
+                byte_end: 4
+            }
+        );
+        assert_eq!((found.line_offset, found.fuzz), (0, 0));
+
+        // Past the end it goes to the last line start, with the offset reported
+        let hunk = Hunk::parse("@@ -5,0 +6 @@\n+six\n").unwrap();
+        let found = hunk.locate(content, &index, 0).unwrap();
+        assert_eq!((found.span.byte_start, found.line_offset), (8, -3));
+
+        // Without a header there is nothing to place it by
+        let hunk = Hunk::parse("+x\n").unwrap();
+        assert!(matches!(
+            hunk.locate(content, &index, 0),
+            Err(EditError::HunkMismatch { .. })
+        ));
+        assert!(hunk.locate("", &LineIndex::new(""), 0).is_ok());
+    }
+}
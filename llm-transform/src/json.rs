@@ -2,6 +2,14 @@ use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
+use crate::edit::{Edit, EditError, PerEditResult};
+use crate::hunk::{DEFAULT_MAX_FUZZ, Hunk, HunkMatch};
+use crate::language::Language;
+use crate::position::{LineEnding, LineIndex, Position, PositionEncoding, Span, with_line_break};
+use crate::search::{Occurrence, regex_edits, select_occurrence};
+use crate::symbol::{Symbol, SymbolTarget, find_symbol};
+use crate::syntax::SyntaxError;
+
 /// Request to apply edits to a file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EditRequest {
@@ -10,31 +18,794 @@ pub struct EditRequest {
     pub execution_id: String,
     /// Path to the file to edit
     pub file_path: String,
-    /// Expected BLAKE3 checksum of the file before editing
+    /// Expected BLAKE3 checksum of the file before editing, or "any" to rely on
+    /// per-edit `expected_text` / `expected_span_checksum` instead
     pub expected_checksum: String,
     /// Edits to apply (will be sorted by byte_start descending)
     #[serde(default)]
     pub edits: Vec<EditJson>,
+    /// Merge identical duplicate edits (reported as skipped) instead of rejecting them as overlapping
+    #[serde(default)]
+    pub merge_duplicates: bool,
+    /// Report the checksum after each individual edit (costs one full hash per edit)
+    #[serde(default)]
+    pub intermediate_checksums: bool,
+    /// Widen spans that split a UTF-8 character to the nearest character boundaries instead of failing
+    #[serde(default)]
+    pub snap_to_char_boundary: bool,
+    /// Relocate edits whose expected_text (plus context) moved; tolerates a whole-file checksum mismatch
+    #[serde(default)]
+    pub relocate: bool,
+    /// Maximum relocation distance in bytes from the requested byte_start (default: whole file)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_drift: Option<usize>,
+    /// Unit of `col_start` / `col_end`: "utf8" (bytes, default), "utf16" (LSP) or "utf32" (characters)
+    #[serde(default)]
+    pub position_encoding: PositionEncoding,
+    /// Run the whole pipeline and report the outcome, but never write the file
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+    /// Reject the batch if it adds parse errors for the file's language
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub validate_syntax: bool,
 }
 
 fn default_execution_id() -> String {
     "auto".to_string()
 }
 
+impl EditRequest {
+    /// Create a request with an "auto" execution_id and default options
+    pub fn new(
+        file_path: impl Into<String>,
+        expected_checksum: impl Into<String>,
+        edits: Vec<EditJson>,
+    ) -> Self {
+        Self {
+            execution_id: default_execution_id(),
+            file_path: file_path.into(),
+            expected_checksum: expected_checksum.into(),
+            edits,
+            merge_duplicates: false,
+            intermediate_checksums: false,
+            snap_to_char_boundary: false,
+            relocate: false,
+            max_drift: None,
+            position_encoding: PositionEncoding::default(),
+            dry_run: false,
+            validate_syntax: false,
+        }
+    }
+}
+
 /// Helper to generate a new execution_id
 pub fn generate_execution_id() -> String {
     Uuid::new_v4().to_string()
 }
 
+/// How an [`EditJson`] addresses the text it changes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EditKind {
+    /// Byte offsets or line/column positions
+    #[default]
+    Span,
+    /// An occurrence of a literal search text (`find`)
+    Find,
+    /// Every match of a regular expression (`pattern`), one edit per match
+    RegexReplace,
+    /// Whole lines `line_start..=line_end`, replaced by `replacement`
+    ReplaceLines,
+    /// Whole lines `line_start..=line_end`, removed
+    DeleteLines,
+    /// New line(s) inserted before `line`
+    InsertBeforeLine,
+    /// New line(s) inserted after `line`
+    InsertAfterLine,
+    /// A unified-diff hunk, or `before_context` + `old_text` + `after_context`
+    Hunk,
+    /// A named item (`symbol`), or part of it (`target`)
+    Symbol,
+}
+
+impl EditKind {
+    fn is_span(&self) -> bool {
+        *self == EditKind::Span
+    }
+
+    /// Name of the kind as used in JSON
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EditKind::Span => "span",
+            EditKind::Find => "find",
+            EditKind::RegexReplace => "regex_replace",
+            EditKind::ReplaceLines => "replace_lines",
+            EditKind::DeleteLines => "delete_lines",
+            EditKind::InsertBeforeLine => "insert_before_line",
+            EditKind::InsertAfterLine => "insert_after_line",
+            EditKind::Hunk => "hunk",
+            EditKind::Symbol => "symbol",
+        }
+    }
+}
+
+/// Where a search-anchored edit inserts its replacement, instead of replacing the match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InsertMode {
+    /// Right before the match
+    Before,
+    /// Right after the match
+    After,
+    /// As new line(s) before the line where the match starts
+    BeforeLine,
+    /// As new line(s) after the line where the match ends
+    AfterLine,
+}
+
 /// Single edit in JSON format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// With the default `kind` ("span"), each end of the span is addressed
+/// either by a byte offset (`byte_start` / `byte_end`) or by a 1-indexed line
+/// and column (`line_start` + `col_start` / `line_end` + `col_end`).
+///
+/// With `kind: "find"`, the span is an occurrence of the literal `find` text
+/// in the verified content, selected by `occurrence` or `expect_unique`.
+///
+/// With `kind: "regex_replace"`, every match of `pattern` becomes its own
+/// edit, with `$1` / `$name` in `replacement` expanded per match.
+///
+/// The line kinds ("replace_lines", "delete_lines", "insert_before_line",
+/// "insert_after_line") address whole lines by `line_start` / `line_end` or
+/// `line`; line breaks in `replacement` are converted to the file's.
+///
+/// With `kind: "hunk"`, the edit is a unified-diff hunk (`hunk`), or its
+/// parts (`before_context`, `old_text`, `after_context` and `replacement`),
+/// located like `patch` does: near the `line` hint, with up to `max_fuzz`
+/// context lines ignored.
+///
+/// With `kind: "symbol"` (or just a `symbol` field), the span is a function,
+/// type or other named item found with the file's tree-sitter grammar, or
+/// its body, signature or name (`target`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EditJson {
+    /// How the edit is addressed (default: "span")
+    #[serde(default, skip_serializing_if = "EditKind::is_span")]
+    pub kind: EditKind,
     /// Byte offset where the edit starts (inclusive)
-    pub byte_start: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub byte_start: Option<usize>,
     /// Byte offset where the edit ends (exclusive)
-    pub byte_end: usize,
-    /// Replacement text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub byte_end: Option<usize>,
+    /// Line where the edit starts (1-indexed, used with col_start)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_start: Option<usize>,
+    /// Column where the edit starts (1-indexed, in the request's position_encoding units)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub col_start: Option<usize>,
+    /// Line where the edit ends (1-indexed, used with col_end)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_end: Option<usize>,
+    /// Column where the edit ends (1-indexed, exclusive, in the request's position_encoding units)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub col_end: Option<usize>,
+    /// Line to insert before or after (1-indexed, kinds "insert_before_line" / "insert_after_line"),
+    /// or where the hunk is expected to start (kind "hunk")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// Literal text to search for (kind "find")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub find: Option<String>,
+    /// Which match of `find` to use (1-indexed, kind "find")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub occurrence: Option<usize>,
+    /// Require `find` to match exactly once (the default when no occurrence is given)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub expect_unique: bool,
+    /// Insert the replacement next to the match instead of replacing it (kind "find")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insert: Option<InsertMode>,
+    /// Regular expression to replace (kind "regex_replace")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Replace at most this many matches (kind "regex_replace")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_replacements: Option<usize>,
+    /// Fail unless `pattern` matches exactly this many times (kind "regex_replace")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_count: Option<usize>,
+    /// Unified-diff hunk, with an optional `@@` header (kind "hunk")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hunk: Option<String>,
+    /// Context lines before `old_text` (kind "hunk")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before_context: Option<String>,
+    /// Lines replaced by `replacement` (kind "hunk")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_text: Option<String>,
+    /// Context lines after `old_text` (kind "hunk")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_context: Option<String>,
+    /// Context lines that may be ignored at each end of the hunk (kind "hunk", default 2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fuzz: Option<usize>,
+    /// Symbol path such as "impl Foo::bar" or "Foo.bar" (kind "symbol")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Part of the symbol to replace: "whole" (default), "body", "signature" or "name"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<SymbolTarget>,
+    /// Replacement text (for "regex_replace", may refer to capture groups as `$1` or `$name`)
+    #[serde(default)]
     pub replacement: String,
+    /// Apply only if the bytes at byte_start..byte_end are exactly this text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_text: Option<String>,
+    /// Apply only if the bytes at byte_start..byte_end have this BLAKE3 checksum
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_span_checksum: Option<String>,
+    /// Text expected immediately before the span (also a relocation anchor)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_before: Option<String>,
+    /// Text expected immediately after the span (also a relocation anchor)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_after: Option<String>,
+}
+
+impl From<&Edit> for EditJson {
+    fn from(edit: &Edit) -> Self {
+        Self {
+            byte_start: Some(edit.byte_start),
+            byte_end: Some(edit.byte_end),
+            replacement: edit.replacement.clone(),
+            expected_text: edit.expected_text.clone(),
+            expected_span_checksum: edit.expected_span_checksum.clone(),
+            context_before: edit.context_before.clone(),
+            context_after: edit.context_after.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl EditJson {
+    /// Resolve to the [`Edit`]s it stands for, checked against `expected_checksum`
+    ///
+    /// Line/column addresses are converted to byte offsets in `content`, and
+    /// search-anchored edits are located in it. A found span carries its
+    /// current text as `expected_text`. A regex edit expands into one edit
+    /// per replaced match (possibly none); every other kind yields one edit.
+    ///
+    /// # Arguments
+    /// * `content` - The file content the addresses refer to
+    /// * `line_index` - Line index of `content`
+    /// * `expected_checksum` - Whole-file checksum the edit is checked against
+    /// * `encoding` - Unit the `col_*` fields are counted in
+    /// * `language` - Language of `content`, for symbol addresses
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Edit>)` - The edits with byte offsets filled in
+    /// * `Err(EditError::InvalidEditSpec)` - Address fields missing or contradictory, or an invalid pattern
+    /// * `Err(EditError::InvalidPosition)` - A line or column is out of range
+    /// * `Err(EditError::SearchMismatch)` - The search text did not match as requested
+    /// * `Err(EditError::MatchCountMismatch)` - The pattern did not match `expected_count` times
+    /// * `Err(EditError::HunkMismatch)` - The hunk was not found, or not unambiguously
+    /// * `Err(EditError::SymbolNotFound)` / `Err(EditError::SymbolAmbiguous)` - The symbol was not found, or not unambiguously
+    pub fn resolve(
+        &self,
+        content: &str,
+        line_index: &LineIndex,
+        expected_checksum: &str,
+        encoding: PositionEncoding,
+        language: Language,
+    ) -> Result<Vec<Edit>, EditError> {
+        Ok(self
+            .resolve_with_hunk_match(content, line_index, expected_checksum, encoding, language)?
+            .0)
+    }
+
+    /// Like [`resolve`](Self::resolve), also returning where a hunk edit's hunk was found
+    ///
+    /// # Returns
+    /// * `Ok((Vec<Edit>, Option<HunkMatch>))` - The edits, and the hunk match for `kind: "hunk"` (`None` otherwise)
+    /// * `Err(EditError)` - As for [`resolve`](Self::resolve)
+    pub fn resolve_with_hunk_match(
+        &self,
+        content: &str,
+        line_index: &LineIndex,
+        expected_checksum: &str,
+        encoding: PositionEncoding,
+        language: Language,
+    ) -> Result<(Vec<Edit>, Option<HunkMatch>), EditError> {
+        let mut located = None;
+        let mut edits = match self.kind {
+            EditKind::Span if self.symbol.is_some() => {
+                vec![self.resolve_symbol(content, language)?]
+            }
+            EditKind::Symbol => vec![self.resolve_symbol(content, language)?],
+            EditKind::Span => vec![self.resolve_span(content, line_index, encoding)?],
+            EditKind::Find => vec![self.resolve_find(content, line_index)?],
+            EditKind::RegexReplace => self.resolve_regex(content)?,
+            EditKind::ReplaceLines
+            | EditKind::DeleteLines
+            | EditKind::InsertBeforeLine
+            | EditKind::InsertAfterLine => {
+                vec![self.resolve_lines(content, line_index)?]
+            }
+            EditKind::Hunk => {
+                let (hunk, hunk_match) = self.locate_hunk(content, line_index)?;
+                located = Some(hunk_match);
+                vec![hunk.to_edit(&hunk_match)]
+            }
+        };
+        for edit in &mut edits {
+            edit.expected_checksum = expected_checksum.to_string();
+            edit.expected_span_checksum = self.expected_span_checksum.clone();
+            // Context given in the request replaces any derived while resolving
+            if self.context_before.is_some() {
+                edit.context_before = self.context_before.clone();
+            }
+            if self.context_after.is_some() {
+                edit.context_after = self.context_after.clone();
+            }
+        }
+        Ok((edits, located))
+    }
+
+    /// Resolve byte offset and line/column addresses
+    fn resolve_span(
+        &self,
+        content: &str,
+        line_index: &LineIndex,
+        encoding: PositionEncoding,
+    ) -> Result<Edit, EditError> {
+        if self.line.is_some() {
+            return Err(EditError::InvalidEditSpec(
+                "line is only used with kinds \"insert_before_line\" and \"insert_after_line\""
+                    .to_string(),
+            ));
+        }
+        let resolve_end = |end, byte, line, column| -> Result<usize, EditError> {
+            match (byte, line, column) {
+                (Some(byte), None, None) => Ok(byte),
+                (None, Some(line), Some(column)) => Ok(line_index.position_to_byte(
+                    content,
+                    Position { line, column },
+                    encoding,
+                )?),
+                (None, None, None) => Err(EditError::InvalidEditSpec(format!(
+                    "missing {0}: set byte_{0} or line_{0} and col_{0}",
+                    end
+                ))),
+                (Some(_), _, _) => Err(EditError::InvalidEditSpec(format!(
+                    "byte_{0} conflicts with line_{0}/col_{0}",
+                    end
+                ))),
+                (None, _, _) => Err(EditError::InvalidEditSpec(format!(
+                    "line_{0} and col_{0} must be set together",
+                    end
+                ))),
+            }
+        };
+
+        Ok(Edit {
+            byte_start: resolve_end("start", self.byte_start, self.line_start, self.col_start)?,
+            byte_end: resolve_end("end", self.byte_end, self.line_end, self.col_end)?,
+            replacement: self.replacement.clone(),
+            expected_text: self.expected_text.clone(),
+            ..Default::default()
+        })
+    }
+
+    /// Resolve a search-anchored edit (kind "find")
+    fn resolve_find(&self, content: &str, line_index: &LineIndex) -> Result<Edit, EditError> {
+        if self.has_span_address() {
+            return Err(EditError::InvalidEditSpec(
+                "byte/line/column fields cannot be used with kind \"find\"".to_string(),
+            ));
+        }
+        let find = self.find.as_deref().ok_or_else(|| {
+            EditError::InvalidEditSpec("kind \"find\" requires a find text".to_string())
+        })?;
+        let occurrence = match (self.occurrence, self.expect_unique) {
+            (Some(_), true) => {
+                return Err(EditError::InvalidEditSpec(
+                    "occurrence conflicts with expect_unique".to_string(),
+                ));
+            }
+            (Some(n), false) => Occurrence::Nth(n),
+            (None, _) => Occurrence::Unique,
+        };
+
+        let found = select_occurrence(content, find, occurrence)?;
+        // Insertions are pinned by the text between the insertion point and
+        // the far end of the match: (offset, replacement, before, after)
+        let context = |span: Span| Some(content[span.byte_start..span.byte_end].to_string());
+        let (byte_offset, replacement, context_before, context_after) = match self.insert {
+            None => {
+                return Ok(Edit {
+                    byte_start: found.byte_start,
+                    byte_end: found.byte_end,
+                    replacement: self.replacement.clone(),
+                    expected_text: Some(
+                        self.expected_text
+                            .clone()
+                            .unwrap_or_else(|| find.to_string()),
+                    ),
+                    ..Default::default()
+                });
+            }
+            Some(InsertMode::Before) => (
+                found.byte_start,
+                self.replacement.clone(),
+                None,
+                context(found),
+            ),
+            Some(InsertMode::After) => (
+                found.byte_end,
+                self.replacement.clone(),
+                context(found),
+                None,
+            ),
+            Some(InsertMode::BeforeLine) => {
+                let line = line_index.line_of(found.byte_start);
+                let span = line_index.line_span(line).unwrap_or(found);
+                let line_break =
+                    nonempty_or_file_break(line_index.line_break(content, line), content);
+                let after = context(Span {
+                    byte_end: found.byte_end,
+                    ..span
+                });
+                (
+                    span.byte_start,
+                    as_lines(&self.replacement, line_break),
+                    None,
+                    after,
+                )
+            }
+            Some(InsertMode::AfterLine) => {
+                let line = line_index.line_of(found.byte_end - 1);
+                let span = line_index.line_span(line).unwrap_or(found);
+                let before = context(Span {
+                    byte_start: found.byte_start,
+                    ..span
+                });
+                match line_index.line_break(content, line) {
+                    // Last line without a line break: start a new line first
+                    Some("") | None => {
+                        let line_break = nonempty_or_file_break(None, content);
+                        let text = as_lines(&self.replacement, line_break);
+                        let text = text.strip_suffix(line_break).unwrap_or(&text);
+                        (
+                            span.byte_end,
+                            format!("{}{}", line_break, text),
+                            before,
+                            None,
+                        )
+                    }
+                    Some(line_break) => (
+                        span.byte_end,
+                        as_lines(&self.replacement, line_break),
+                        before,
+                        None,
+                    ),
+                }
+            }
+        };
+
+        Ok(Edit {
+            byte_start: byte_offset,
+            byte_end: byte_offset,
+            replacement,
+            expected_text: self.expected_text.clone(),
+            context_before,
+            context_after,
+            ..Default::default()
+        })
+    }
+
+    /// Resolve a regex search-and-replace (kind "regex_replace")
+    fn resolve_regex(&self, content: &str) -> Result<Vec<Edit>, EditError> {
+        if self.has_span_address() {
+            return Err(EditError::InvalidEditSpec(
+                "byte/line/column fields cannot be used with kind \"regex_replace\"".to_string(),
+            ));
+        }
+        if self.expected_text.is_some() {
+            return Err(EditError::InvalidEditSpec(
+                "expected_text cannot be used with kind \"regex_replace\": each match's own text is its precondition"
+                    .to_string(),
+            ));
+        }
+        let pattern = self.pattern.as_deref().ok_or_else(|| {
+            EditError::InvalidEditSpec("kind \"regex_replace\" requires a pattern".to_string())
+        })?;
+
+        let edits = regex_edits(
+            content,
+            pattern,
+            &self.replacement,
+            self.max_replacements,
+            self.expected_count,
+        )?;
+        // These describe one span; copied onto every match they would fail or relocate all but one
+        let single_span_fields = [
+            (
+                "expected_span_checksum",
+                self.expected_span_checksum.is_some(),
+            ),
+            ("context_before", self.context_before.is_some()),
+            ("context_after", self.context_after.is_some()),
+        ];
+        if let Some((field, _)) = single_span_fields
+            .iter()
+            .find(|(_, set)| *set)
+            .filter(|_| edits.len() > 1)
+        {
+            return Err(EditError::InvalidEditSpec(format!(
+                "{} applies to a single match, but pattern {:?} matched {} times (set max_replacements or expected_count to 1)",
+                field,
+                pattern,
+                edits.len()
+            )));
+        }
+        Ok(edits)
+    }
+
+    /// Resolve a line-oriented edit (kinds "replace_lines", "delete_lines",
+    /// "insert_before_line" and "insert_after_line")
+    ///
+    /// Replaced and inserted text is terminated by a line break, except on a
+    /// last line that has none, so the file keeps (or lacks) its trailing
+    /// newline.
+    fn resolve_lines(&self, content: &str, line_index: &LineIndex) -> Result<Edit, EditError> {
+        let kind = self.kind.as_str();
+        if [self.byte_start, self.byte_end, self.col_start, self.col_end]
+            .iter()
+            .any(Option::is_some)
+        {
+            return Err(EditError::InvalidEditSpec(format!(
+                "byte/column fields cannot be used with kind \"{}\"",
+                kind
+            )));
+        }
+        let inserting = matches!(
+            self.kind,
+            EditKind::InsertBeforeLine | EditKind::InsertAfterLine
+        );
+        let (first, last) = if inserting {
+            if self.line_start.is_some() || self.line_end.is_some() {
+                return Err(EditError::InvalidEditSpec(format!(
+                    "kind \"{}\" takes line, not line_start/line_end",
+                    kind
+                )));
+            }
+            let line = self.line.ok_or_else(|| {
+                EditError::InvalidEditSpec(format!("kind \"{}\" requires line", kind))
+            })?;
+            (line, line)
+        } else {
+            if self.line.is_some() {
+                return Err(EditError::InvalidEditSpec(format!(
+                    "kind \"{}\" takes line_start/line_end, not line",
+                    kind
+                )));
+            }
+            let first = self.line_start.ok_or_else(|| {
+                EditError::InvalidEditSpec(format!("kind \"{}\" requires line_start", kind))
+            })?;
+            (first, self.line_end.unwrap_or(first))
+        };
+        if last < first {
+            return Err(EditError::InvalidEditSpec(format!(
+                "line_end ({}) is before line_start ({})",
+                last, first
+            )));
+        }
+        if self.kind == EditKind::DeleteLines && !self.replacement.is_empty() {
+            return Err(EditError::InvalidEditSpec(
+                "kind \"delete_lines\" takes no replacement".to_string(),
+            ));
+        }
+
+        let span = line_index.lines_span(first, last)?;
+        let last_break = line_index.line_break(content, last).unwrap_or("");
+        let line_break = nonempty_or_file_break(Some(last_break), content);
+        let text = with_line_break(&self.replacement, line_break);
+        // The last line of a file without a trailing newline
+        let ends_file = last_break.is_empty();
+
+        let (span, replacement) = match self.kind {
+            EditKind::InsertBeforeLine => (
+                Span {
+                    byte_end: span.byte_start,
+                    ..span
+                },
+                as_lines(&text, line_break),
+            ),
+            EditKind::InsertAfterLine if ends_file => {
+                let text = as_lines(&text, line_break);
+                let text = text.strip_suffix(line_break).unwrap_or(&text);
+                (
+                    Span {
+                        byte_start: span.byte_end,
+                        ..span
+                    },
+                    format!("{}{}", line_break, text),
+                )
+            }
+            EditKind::InsertAfterLine => (
+                Span {
+                    byte_start: span.byte_end,
+                    ..span
+                },
+                as_lines(&text, line_break),
+            ),
+            // Removing the last lines also removes the line break before them
+            _ if text.is_empty() && ends_file && first > 1 => {
+                let previous_break = line_index.line_break(content, first - 1).unwrap_or("");
+                (
+                    Span {
+                        byte_start: span.byte_start - previous_break.len(),
+                        ..span
+                    },
+                    String::new(),
+                )
+            }
+            _ if ends_file => {
+                let text = as_lines(&text, line_break);
+                let text = text.strip_suffix(line_break).unwrap_or(&text).to_string();
+                (span, text)
+            }
+            _ => (span, as_lines(&text, line_break)),
+        };
+        // Replaced lines must still be there when the edit is applied, which
+        // also lets the edit run without a verified whole-file checksum
+        let expected_text = match self.kind {
+            EditKind::InsertBeforeLine | EditKind::InsertAfterLine => self.expected_text.clone(),
+            _ => Some(
+                self.expected_text
+                    .clone()
+                    .unwrap_or_else(|| content[span.byte_start..span.byte_end].to_string()),
+            ),
+        };
+
+        Ok(Edit {
+            byte_start: span.byte_start,
+            byte_end: span.byte_end,
+            replacement,
+            expected_text,
+            ..Default::default()
+        })
+    }
+
+    /// Locate a diff-hunk edit (kind "hunk") in `content`
+    ///
+    /// The hunk's line breaks are converted to the file's line ending first.
+    ///
+    /// # Returns
+    /// * `Ok((Hunk, HunkMatch))` - The hunk as matched, and where and how it was found
+    /// * `Err(EditError::InvalidEditSpec)` - Missing, conflicting or malformed hunk fields
+    /// * `Err(EditError::HunkMismatch)` - The hunk was not found, or not unambiguously
+    pub fn locate_hunk(
+        &self,
+        content: &str,
+        line_index: &LineIndex,
+    ) -> Result<(Hunk, HunkMatch), EditError> {
+        if [
+            self.byte_start,
+            self.byte_end,
+            self.line_start,
+            self.col_start,
+            self.line_end,
+            self.col_end,
+        ]
+        .iter()
+        .any(Option::is_some)
+        {
+            return Err(EditError::InvalidEditSpec(
+                "byte/line/column fields cannot be used with kind \"hunk\" (use line as a hint)"
+                    .to_string(),
+            ));
+        }
+        let parts = [&self.before_context, &self.old_text, &self.after_context];
+        let hunk = match &self.hunk {
+            Some(_) if parts.iter().any(|part| part.is_some()) || !self.replacement.is_empty() => {
+                return Err(EditError::InvalidEditSpec(
+                    "hunk conflicts with before_context/old_text/after_context/replacement"
+                        .to_string(),
+                ));
+            }
+            Some(text) => Hunk::parse(text)?,
+            None if parts.iter().all(|part| part.is_none()) => {
+                return Err(EditError::InvalidEditSpec(
+                    "kind \"hunk\" requires a hunk or old_text with context".to_string(),
+                ));
+            }
+            None => Hunk {
+                line: None,
+                before_context: self.before_context.clone().unwrap_or_default(),
+                old_text: self.old_text.clone().unwrap_or_default(),
+                new_text: self.replacement.clone(),
+                after_context: self.after_context.clone().unwrap_or_default(),
+            },
+        };
+        let hunk = Hunk {
+            line: self.line.or(hunk.line),
+            ..hunk
+        };
+        let hunk = match LineEnding::detect(content).and_then(LineEnding::as_str) {
+            Some(line_break) => hunk.with_line_break(line_break),
+            None => hunk,
+        };
+
+        let hunk_match = hunk.locate(
+            content,
+            line_index,
+            self.max_fuzz.unwrap_or(DEFAULT_MAX_FUZZ),
+        )?;
+        Ok((hunk, hunk_match))
+    }
+
+    /// Resolve a symbol address (kind "symbol")
+    fn resolve_symbol(&self, content: &str, language: Language) -> Result<Edit, EditError> {
+        if self.has_span_address() {
+            return Err(EditError::InvalidEditSpec(
+                "byte/line/column fields cannot be used with kind \"symbol\"".to_string(),
+            ));
+        }
+        let query = self.symbol.as_deref().ok_or_else(|| {
+            EditError::InvalidEditSpec("kind \"symbol\" requires a symbol".to_string())
+        })?;
+
+        let span =
+            find_symbol(content, language, query)?.target_span(self.target.unwrap_or_default())?;
+        Ok(Edit {
+            byte_start: span.byte_start,
+            byte_end: span.byte_end,
+            replacement: self.replacement.clone(),
+            expected_text: Some(
+                self.expected_text
+                    .clone()
+                    .unwrap_or_else(|| content[span.byte_start..span.byte_end].to_string()),
+            ),
+            ..Default::default()
+        })
+    }
+
+    /// Whether any byte offset, line or column field is set
+    fn has_span_address(&self) -> bool {
+        [
+            self.byte_start,
+            self.byte_end,
+            self.line_start,
+            self.col_start,
+            self.line_end,
+            self.col_end,
+            self.line,
+        ]
+        .iter()
+        .any(Option::is_some)
+    }
+}
+
+/// `line_break` if it is a real line break, otherwise the file's line ending (LF if none or mixed)
+fn nonempty_or_file_break<'a>(line_break: Option<&'a str>, content: &str) -> &'a str {
+    match line_break {
+        Some(line_break) if !line_break.is_empty() => line_break,
+        _ => LineEnding::detect(content)
+            .and_then(LineEnding::as_str)
+            .unwrap_or("\n"),
+    }
+}
+
+/// `text` as whole lines: terminated by `line_break` unless empty or already terminated
+fn as_lines(text: &str, line_break: &str) -> String {
+    if text.is_empty() || text.ends_with('\n') || text.ends_with('\r') {
+        text.to_string()
+    } else {
+        format!("{}{}", text, line_break)
+    }
 }
 
 /// Response after applying edits
@@ -54,21 +825,40 @@ pub struct EditResponse {
     pub skipped_count: usize,
     /// Number of edits that failed
     pub error_count: usize,
+    /// Number of edits rolled back (transactional mode only)
+    #[serde(default)]
+    pub rolled_back_count: usize,
     /// Individual edit results
     pub edits: Vec<PerEditResultJson>,
     /// Error message if operation failed
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Request that reverts this batch (feed back as a new EditRequest)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undo: Option<EditRequest>,
+    /// Unified diff from the original to the edited content (if requested, and always in a dry run)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+    /// Whether this was a dry run: the file was not written
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+    /// Content after the edits (only if requested)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_content: Option<String>,
+    /// Parse errors the edits would have introduced (only with syntax validation)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub syntax_errors: Option<Vec<SyntaxError>>,
 }
 
 /// Result of a single edit in JSON format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PerEditResultJson {
-    /// Original byte offset of this edit
-    pub byte_offset: usize,
-    /// Result type: "applied", "skipped", or "error"
+    /// Original byte offset of this edit (absent for an edit that never resolved to a span)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub byte_offset: Option<usize>,
+    /// Result type: "applied", "skipped", "error", or "rolled_back"
     pub status: String,
-    /// New checksum after this edit (only if applied)
+    /// New checksum after this edit (only if applied with intermediate checksums enabled)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub new_checksum: Option<String>,
     /// Byte shift introduced by this edit (only if applied)
@@ -77,6 +867,98 @@ pub struct PerEditResultJson {
     /// Reason or error message (for skipped/error)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    /// Span the edit was actually applied to, if it was relocated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relocated_span: Option<Span>,
+    /// Distance in bytes from the requested byte_start, if the edit was relocated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drift: Option<i64>,
+    /// Candidate spans for a failed relocation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub candidates: Option<Vec<Span>>,
+    /// Lines between the hinted and the actual position of a hunk
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_offset: Option<i64>,
+    /// Context lines ignored at each end to locate a hunk
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuzz: Option<usize>,
+    /// Candidate symbols for a symbol address that was not found or is ambiguous
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbols: Option<Vec<Symbol>>,
+}
+
+impl From<PerEditResult> for PerEditResultJson {
+    fn from(result: PerEditResult) -> Self {
+        match result {
+            PerEditResult::Applied {
+                byte_offset,
+                new_checksum,
+                byte_shift,
+                relocation,
+            } => Self {
+                byte_offset: Some(byte_offset),
+                status: "applied".to_string(),
+                new_checksum,
+                byte_shift: Some(byte_shift),
+                relocated_span: relocation.map(|r| r.span),
+                drift: relocation.map(|r| r.drift),
+                ..Default::default()
+            },
+            PerEditResult::Skipped {
+                byte_offset,
+                reason,
+            } => Self {
+                byte_offset: Some(byte_offset),
+                status: "skipped".to_string(),
+                reason: Some(reason),
+                ..Default::default()
+            },
+            PerEditResult::Error {
+                byte_offset,
+                error,
+                candidates,
+            } => Self {
+                byte_offset: Some(byte_offset),
+                status: "error".to_string(),
+                reason: Some(error),
+                candidates: (!candidates.is_empty()).then_some(candidates),
+                ..Default::default()
+            },
+            PerEditResult::RolledBack { byte_offset } => Self {
+                byte_offset: Some(byte_offset),
+                status: "rolled_back".to_string(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl PerEditResultJson {
+    /// Result for an edit that could not be resolved to a byte span
+    ///
+    /// `byte_offset` is the edit's requested `byte_start`, if it had one.
+    pub fn unresolved(byte_offset: Option<usize>, error: &EditError) -> Self {
+        let candidates = error.candidates();
+        let symbols = error.symbol_candidates();
+        Self {
+            byte_offset,
+            status: "error".to_string(),
+            reason: Some(error.to_string()),
+            candidates: (!candidates.is_empty()).then(|| candidates.to_vec()),
+            symbols: (!symbols.is_empty()).then(|| symbols.to_vec()),
+            ..Default::default()
+        }
+    }
+
+    /// Result for an edit that was not applied because another edit of the batch failed
+    pub fn not_attempted(byte_offset: Option<usize>) -> Self {
+        Self {
+            byte_offset,
+            status: "skipped".to_string(),
+            reason: Some("not attempted: another edit in the batch failed".to_string()),
+            ..Default::default()
+        }
+    }
 }
 
 impl EditResponse {
@@ -97,9 +979,29 @@ impl EditResponse {
             applied_count,
             skipped_count,
             error_count: 0,
+            rolled_back_count: 0,
             edits,
             error: None,
+            undo: None,
+            diff: None,
+            dry_run: false,
+            final_content: None,
+            syntax_errors: None,
+        }
+    }
+
+    /// Report where each located hunk was found on its edit's result
+    ///
+    /// `hunk_matches` has one entry per result, in the same order (`None`
+    /// for edits that are not hunks).
+    pub fn with_hunk_matches(mut self, hunk_matches: &[Option<HunkMatch>]) -> Self {
+        for (result, hunk_match) in self.edits.iter_mut().zip(hunk_matches) {
+            if let Some(hunk_match) = hunk_match {
+                result.line_offset = Some(hunk_match.line_offset);
+                result.fuzz = Some(hunk_match.fuzz);
+            }
         }
+        self
     }
 
     /// Create a failure response
@@ -112,8 +1014,14 @@ impl EditResponse {
             applied_count: 0,
             skipped_count: 0,
             error_count: 0,
+            rolled_back_count: 0,
             edits: Vec::new(),
             error: Some(error),
+            undo: None,
+            diff: None,
+            dry_run: false,
+            final_content: None,
+            syntax_errors: None,
         }
     }
 }
@@ -182,44 +1090,50 @@ impl ExecutionLog {
 
     /// Add a "started" event
     pub fn started(&mut self, file_path: &str) {
-        self.add(ExecutionLogEntry::new("started")
-            .with_message(format!("Processing file: {}", file_path)));
+        self.add(
+            ExecutionLogEntry::new("started")
+                .with_message(format!("Processing file: {}", file_path)),
+        );
     }
 
     /// Add an "edit_applied" event
     pub fn edit_applied(&mut self, byte_offset: usize, new_checksum: &str) {
-        self.add(ExecutionLogEntry::new("edit_applied")
-            .with_byte_offset(byte_offset)
-            .with_message(format!("New checksum: {}", new_checksum)));
+        self.add(
+            ExecutionLogEntry::new("edit_applied")
+                .with_byte_offset(byte_offset)
+                .with_message(format!("New checksum: {}", new_checksum)),
+        );
     }
 
     /// Add an "edit_skipped" event
     pub fn edit_skipped(&mut self, byte_offset: usize, reason: &str) {
-        self.add(ExecutionLogEntry::new("edit_skipped")
-            .with_byte_offset(byte_offset)
-            .with_message(reason.to_string()));
+        self.add(
+            ExecutionLogEntry::new("edit_skipped")
+                .with_byte_offset(byte_offset)
+                .with_message(reason.to_string()),
+        );
     }
 
     /// Add an "edit_error" event
     pub fn edit_error(&mut self, byte_offset: usize, error: &str) {
-        self.add(ExecutionLogEntry::new("edit_error")
-            .with_byte_offset(byte_offset)
-            .with_message(error.to_string()));
+        self.add(
+            ExecutionLogEntry::new("edit_error")
+                .with_byte_offset(byte_offset)
+                .with_message(error.to_string()),
+        );
     }
 
     /// Add a "completed" event
     pub fn completed(&mut self, applied_count: usize, final_checksum: &str) {
-        self.add(ExecutionLogEntry::new("completed")
-            .with_message(format!(
-                "Applied {} edits, final checksum: {}",
-                applied_count, final_checksum
-            )));
+        self.add(ExecutionLogEntry::new("completed").with_message(format!(
+            "Applied {} edits, final checksum: {}",
+            applied_count, final_checksum
+        )));
     }
 
     /// Add a "failed" event
     pub fn failed(&mut self, error: &str) {
-        self.add(ExecutionLogEntry::new("failed")
-            .with_message(error.to_string()));
+        self.add(ExecutionLogEntry::new("failed").with_message(error.to_string()));
     }
 }
 
@@ -234,6 +1148,8 @@ fn current_timestamp_ms() -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::edit::{ANY_CHECKSUM, apply_edits};
+    use crate::position::PositionError;
 
     #[test]
     fn test_edit_request_serialize() {
@@ -241,13 +1157,20 @@ mod tests {
             execution_id: "test-exec-123".to_string(),
             file_path: "/path/to/file.txt".to_string(),
             expected_checksum: "abc123def456".to_string(),
-            edits: vec![
-                EditJson {
-                    byte_start: 0,
-                    byte_end: 5,
-                    replacement: "Hello".to_string(),
-                },
-            ],
+            edits: vec![EditJson {
+                byte_start: Some(0),
+                byte_end: Some(5),
+                replacement: "Hello".to_string(),
+                ..Default::default()
+            }],
+            merge_duplicates: false,
+            intermediate_checksums: false,
+            snap_to_char_boundary: false,
+            relocate: false,
+            max_drift: None,
+            position_encoding: PositionEncoding::Utf8,
+            dry_run: false,
+            validate_syntax: false,
         };
 
         let json = serde_json::to_string(&request);
@@ -286,9 +1209,11 @@ mod tests {
         assert_eq!(request.file_path, "/another/file.txt");
         assert_eq!(request.expected_checksum, "xyz789");
         assert_eq!(request.edits.len(), 1);
-        assert_eq!(request.edits[0].byte_start, 10);
-        assert_eq!(request.edits[0].byte_end, 20);
+        assert_eq!(request.edits[0].byte_start, Some(10));
+        assert_eq!(request.edits[0].byte_end, Some(20));
         assert_eq!(request.edits[0].replacement, "World");
+        assert_eq!(request.edits[0].expected_text, None);
+        assert!(!request.merge_duplicates);
     }
 
     #[test]
@@ -361,4 +1286,564 @@ mod tests {
         let json = serde_json::to_string(&entry);
         assert!(json.is_ok());
     }
+
+    #[test]
+    fn test_per_edit_result_json_relocation() {
+        let applied = PerEditResultJson::from(PerEditResult::Applied {
+            byte_offset: 16,
+            new_checksum: None,
+            byte_shift: 0,
+            relocation: Some(crate::edit::Relocation {
+                span: Span {
+                    byte_start: 30,
+                    byte_end: 33,
+                },
+                drift: 14,
+            }),
+        });
+        let json = serde_json::to_value(&applied).unwrap();
+        assert_eq!(json["status"], "applied");
+        assert_eq!(json["relocated_span"]["byte_start"], 30);
+        assert_eq!(json["drift"], 14);
+        assert!(json.get("candidates").is_none());
+
+        let failed = PerEditResultJson::from(PerEditResult::Error {
+            byte_offset: 0,
+            error: "ambiguous".to_string(),
+            candidates: vec![
+                Span {
+                    byte_start: 4,
+                    byte_end: 5,
+                },
+                Span {
+                    byte_start: 11,
+                    byte_end: 12,
+                },
+            ],
+        });
+        let json = serde_json::to_value(&failed).unwrap();
+        assert_eq!(json["status"], "error");
+        assert_eq!(json["candidates"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_undo_request_round_trip() {
+        let undo_edit = Edit {
+            byte_start: 7,
+            byte_end: 11,
+            replacement: "world".to_string(),
+            expected_checksum: "final".to_string(),
+            expected_text: Some("Rust".to_string()),
+            ..Default::default()
+        };
+        let undo = EditRequest::new("/tmp/file.txt", "final", vec![EditJson::from(&undo_edit)]);
+
+        let json = serde_json::to_string(&undo).unwrap();
+        let parsed: EditRequest = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.execution_id, "auto");
+        assert_eq!(parsed.expected_checksum, "final");
+        assert_eq!(parsed.edits.len(), 1);
+        assert_eq!(parsed.edits[0].expected_text.as_deref(), Some("Rust"));
+        assert_eq!(
+            resolve_in("Hello Rust", &parsed.edits[0], "final").unwrap(),
+            vec![undo_edit]
+        );
+    }
+
+    #[test]
+    fn test_resolve_line_column() {
+        let content = "fn main() {\n    old();\n}\n";
+        let json = r#"{"line_start": 2, "col_start": 5, "line_end": 2, "col_end": 8, "replacement": "new"}"#;
+        let edit_json: EditJson = serde_json::from_str(json).unwrap();
+
+        let edit = resolve_in(content, &edit_json, "abc").unwrap().remove(0);
+        assert_eq!(edit.byte_start, 16);
+        assert_eq!(edit.byte_end, 19);
+        assert_eq!(&content[edit.byte_start..edit.byte_end], "old");
+        assert_eq!(edit.expected_checksum, "abc");
+
+        // Mixed addressing: byte start, line/column end
+        let mixed = EditJson {
+            byte_start: Some(12),
+            line_end: Some(3),
+            col_end: Some(1),
+            ..Default::default()
+        };
+        let edit = resolve_in(content, &mixed, "abc").unwrap().remove(0);
+        assert_eq!((edit.byte_start, edit.byte_end), (12, 23));
+    }
+
+    #[test]
+    fn test_resolve_utf16_columns() {
+        let json = r#"{"file_path": "f", "expected_checksum": "any", "position_encoding": "utf16",
+            "edits": [{"line_start": 1, "col_start": 4, "line_end": 1, "col_end": 6, "replacement": "X"}]}"#;
+        let request: EditRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.position_encoding, PositionEncoding::Utf16);
+
+        // '😀' is two UTF-16 code units, so "ok" starts at UTF-16 column 4
+        let content = "😀 ok";
+        let edit = request.edits[0]
+            .resolve(
+                content,
+                &LineIndex::new(content),
+                "any",
+                request.position_encoding,
+                Language::Unknown,
+            )
+            .unwrap()
+            .remove(0);
+        assert_eq!(&content[edit.byte_start..edit.byte_end], "ok");
+    }
+
+    #[test]
+    fn test_resolve_invalid_address() {
+        let content = "one\ntwo";
+
+        let past_last_line = EditJson {
+            line_start: Some(3),
+            col_start: Some(1),
+            byte_end: Some(0),
+            ..Default::default()
+        };
+        match resolve_in(content, &past_last_line, "abc") {
+            Err(EditError::InvalidPosition(PositionError::LineOutOfRange {
+                line: 3,
+                line_count: 2,
+            })) => {}
+            other => panic!("Expected LineOutOfRange, got {:?}", other),
+        }
+
+        let past_line_end = EditJson {
+            byte_start: Some(0),
+            line_end: Some(2),
+            col_end: Some(9),
+            ..Default::default()
+        };
+        match resolve_in(content, &past_line_end, "abc") {
+            Err(EditError::InvalidPosition(PositionError::ColumnOutOfRange {
+                max_column: 4,
+                ..
+            })) => {}
+            other => panic!("Expected ColumnOutOfRange, got {:?}", other),
+        }
+
+        let missing_end = EditJson {
+            byte_start: Some(0),
+            ..Default::default()
+        };
+        assert!(matches!(
+            resolve_in(content, &missing_end, "abc"),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+
+        let missing_column = EditJson {
+            byte_start: Some(0),
+            line_end: Some(1),
+            ..Default::default()
+        };
+        assert!(matches!(
+            resolve_in(content, &missing_column, "abc"),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+
+        let conflicting = EditJson {
+            byte_start: Some(0),
+            line_start: Some(1),
+            col_start: Some(1),
+            byte_end: Some(1),
+            ..Default::default()
+        };
+        assert!(matches!(
+            resolve_in(content, &conflicting, "abc"),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+    }
+
+    /// Resolve `edit_json` against `content` with byte columns and no language
+    fn resolve_in(
+        content: &str,
+        edit_json: &EditJson,
+        expected_checksum: &str,
+    ) -> Result<Vec<Edit>, EditError> {
+        edit_json.resolve(
+            content,
+            &LineIndex::new(content),
+            expected_checksum,
+            PositionEncoding::Utf8,
+            Language::Unknown,
+        )
+    }
+
+    fn apply_resolved(content: &str, edit_json: &EditJson) -> String {
+        let edit = resolve_in(content, edit_json, "any").unwrap().remove(0);
+        format!(
+            "{}{}{}",
+            &content[..edit.byte_start],
+            edit.replacement,
+            &content[edit.byte_end..]
+        )
+    }
+
+    #[test]
+    fn test_resolve_find() {
+        let content = "let a = 1;\nlet b = 1;\nlet c = 1;";
+
+        let json = r#"{"kind": "find", "find": "= 1", "occurrence": 2, "replacement": "= 2"}"#;
+        let edit_json: EditJson = serde_json::from_str(json).unwrap();
+        let edit = resolve_in(content, &edit_json, "any").unwrap().remove(0);
+        assert_eq!((edit.byte_start, edit.byte_end), (17, 20));
+        assert_eq!(edit.expected_text.as_deref(), Some("= 1"));
+
+        let find = |find: &str, insert, replacement: &str| EditJson {
+            kind: EditKind::Find,
+            find: Some(find.to_string()),
+            insert,
+            replacement: replacement.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            apply_resolved(content, &find("b", Some(InsertMode::After), "2")),
+            "let a = 1;\nlet b2 = 1;\nlet c = 1;"
+        );
+        assert_eq!(
+            apply_resolved(
+                content,
+                &find("let b", Some(InsertMode::AfterLine), "// after b")
+            ),
+            "let a = 1;\nlet b = 1;\n// after b\nlet c = 1;"
+        );
+        assert_eq!(
+            apply_resolved(
+                content,
+                &find("let b", Some(InsertMode::BeforeLine), "// before b\n")
+            ),
+            "let a = 1;\n// before b\nlet b = 1;\nlet c = 1;"
+        );
+        // Last line has no line break
+        assert_eq!(
+            apply_resolved(
+                content,
+                &find("let c", Some(InsertMode::AfterLine), "// end")
+            ),
+            "let a = 1;\nlet b = 1;\nlet c = 1;\n// end"
+        );
+        // New lines use the file's line ending
+        assert_eq!(
+            apply_resolved("a\r\nb\r\n", &find("a", Some(InsertMode::AfterLine), "x")),
+            "a\r\nx\r\nb\r\n"
+        );
+
+        // Insertions are pinned by the matched text next to them
+        let resolve = |edit_json: EditJson| resolve_in(content, &edit_json, "any").unwrap();
+        let before = resolve(find("b =", Some(InsertMode::Before), "x"));
+        assert_eq!(
+            (
+                before[0].context_before.as_deref(),
+                before[0].context_after.as_deref()
+            ),
+            (None, Some("b ="))
+        );
+        let after_line = resolve(find("b =", Some(InsertMode::AfterLine), "x"));
+        assert_eq!(after_line[0].context_before.as_deref(), Some("b = 1;\n"));
+        assert!(
+            apply_edits(content, ANY_CHECKSUM, &after_line)
+                .unwrap()
+                .is_complete_success()
+        );
+        let changed = content.replace("b = 1", "b = 2");
+        assert_eq!(
+            apply_edits(&changed, ANY_CHECKSUM, &after_line)
+                .unwrap()
+                .error_count,
+            1
+        );
+    }
+
+    #[test]
+    fn test_resolve_find_mismatch() {
+        let content = "x = 1;\ny = 1;\n";
+        let edit_json = EditJson {
+            kind: EditKind::Find,
+            find: Some("= 1".to_string()),
+            expect_unique: true,
+            ..Default::default()
+        };
+
+        let error = resolve_in(content, &edit_json, "any").unwrap_err();
+        assert!(matches!(&error, EditError::SearchMismatch { matches, .. } if matches.len() == 2));
+        assert_eq!(
+            error.to_string(),
+            "Text \"= 1\" is not unique: 2 matches at byte(s) 2, 9"
+        );
+
+        let result = PerEditResultJson::unresolved(None, &error);
+        assert_eq!(result.status, "error");
+        // No span was ever resolved, so no offset is reported
+        assert!(
+            serde_json::to_value(&result)
+                .unwrap()
+                .get("byte_offset")
+                .is_none()
+        );
+        assert_eq!(result.candidates.map(|c| c.len()), Some(2));
+
+        let with_offset = EditJson {
+            byte_start: Some(0),
+            ..edit_json.clone()
+        };
+        assert!(matches!(
+            resolve_in(content, &with_offset, "any"),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_lines() {
+        let lines = |kind, line_start, line_end, replacement: &str| EditJson {
+            kind,
+            line_start: Some(line_start),
+            line_end: Some(line_end),
+            replacement: replacement.to_string(),
+            ..Default::default()
+        };
+        let insert = |kind, line, replacement: &str| EditJson {
+            kind,
+            line: Some(line),
+            replacement: replacement.to_string(),
+            ..Default::default()
+        };
+
+        let content = "one\ntwo\nthree\n";
+        assert_eq!(
+            apply_resolved(content, &lines(EditKind::ReplaceLines, 2, 3, "2\n3")),
+            "one\n2\n3\n"
+        );
+        assert_eq!(
+            apply_resolved(content, &lines(EditKind::DeleteLines, 1, 2, "")),
+            "three\n"
+        );
+        assert_eq!(
+            apply_resolved(content, &lines(EditKind::DeleteLines, 3, 3, "")),
+            "one\ntwo\n"
+        );
+        assert_eq!(
+            apply_resolved(content, &insert(EditKind::InsertBeforeLine, 1, "zero")),
+            "zero\none\ntwo\nthree\n"
+        );
+        assert_eq!(
+            apply_resolved(content, &insert(EditKind::InsertAfterLine, 3, "four")),
+            "one\ntwo\nthree\nfour\n"
+        );
+
+        // No trailing newline: the file still ends without one
+        let content = "one\ntwo";
+        assert_eq!(
+            apply_resolved(content, &lines(EditKind::ReplaceLines, 2, 2, "2\n")),
+            "one\n2"
+        );
+        assert_eq!(
+            apply_resolved(content, &lines(EditKind::DeleteLines, 2, 2, "")),
+            "one"
+        );
+        assert_eq!(
+            apply_resolved(content, &insert(EditKind::InsertAfterLine, 2, "three")),
+            "one\ntwo\nthree"
+        );
+
+        // Inserted text takes the file's line ending
+        let content = "a\r\nb\r\n";
+        assert_eq!(
+            apply_resolved(content, &lines(EditKind::ReplaceLines, 1, 1, "x\ny")),
+            "x\r\ny\r\nb\r\n"
+        );
+        assert_eq!(
+            apply_resolved(content, &insert(EditKind::InsertAfterLine, 1, "x\n")),
+            "a\r\nx\r\nb\r\n"
+        );
+    }
+
+    #[test]
+    fn test_resolve_lines_any_checksum() {
+        let content = "one\ntwo\nthree\n";
+        let resolve = |edit_json: EditJson| resolve_in(content, &edit_json, "any").unwrap();
+
+        // Replaced and deleted lines carry their current text as the precondition
+        let edits: Vec<Edit> = [
+            EditJson {
+                kind: EditKind::ReplaceLines,
+                line_start: Some(1),
+                replacement: "1".to_string(),
+                ..Default::default()
+            },
+            EditJson {
+                kind: EditKind::DeleteLines,
+                line_start: Some(3),
+                ..Default::default()
+            },
+        ]
+        .into_iter()
+        .flat_map(resolve)
+        .collect();
+        assert_eq!(edits[0].expected_text.as_deref(), Some("one\n"));
+        let result = apply_edits(content, ANY_CHECKSUM, &edits).unwrap();
+        assert_eq!(result.final_content, "1\ntwo\n");
+
+        // An explicit expected_text is kept
+        let edits = resolve(EditJson {
+            kind: EditKind::ReplaceLines,
+            line_start: Some(2),
+            expected_text: Some("TWO\n".to_string()),
+            ..Default::default()
+        });
+        let result = apply_edits(content, ANY_CHECKSUM, &edits).unwrap();
+        assert_eq!(result.error_count, 1);
+    }
+
+    #[test]
+    fn test_resolve_lines_invalid() {
+        let content = "one\ntwo\n";
+        let resolve = |edit_json: EditJson| resolve_in(content, &edit_json, "any");
+
+        assert!(matches!(
+            resolve(EditJson {
+                kind: EditKind::ReplaceLines,
+                line_start: Some(3),
+                ..Default::default()
+            }),
+            Err(EditError::InvalidPosition(PositionError::LineOutOfRange {
+                line: 3,
+                line_count: 2
+            }))
+        ));
+        assert!(matches!(
+            resolve(EditJson {
+                kind: EditKind::ReplaceLines,
+                line_start: Some(2),
+                line_end: Some(1),
+                ..Default::default()
+            }),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+        assert!(matches!(
+            resolve(EditJson {
+                kind: EditKind::InsertAfterLine,
+                line_start: Some(1),
+                ..Default::default()
+            }),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+        assert!(matches!(
+            resolve(EditJson {
+                kind: EditKind::DeleteLines,
+                line_start: Some(1),
+                replacement: "x".to_string(),
+                ..Default::default()
+            }),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+        assert!(matches!(
+            resolve(EditJson {
+                kind: EditKind::ReplaceLines,
+                line_start: Some(1),
+                col_start: Some(1),
+                ..Default::default()
+            }),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_hunk() {
+        let content = "fn a() {\r\n    one();\r\n}\r\n";
+        let hunk = EditJson {
+            kind: EditKind::Hunk,
+            before_context: Some("fn a() {\n".to_string()),
+            old_text: Some("    one();\n".to_string()),
+            after_context: Some("}\n".to_string()),
+            replacement: "    two();\n".to_string(),
+            ..Default::default()
+        };
+
+        // Hunk line breaks follow the file's
+        assert_eq!(
+            apply_resolved(content, &hunk),
+            "fn a() {\r\n    two();\r\n}\r\n"
+        );
+        let (_, found) = hunk.locate_hunk(content, &LineIndex::new(content)).unwrap();
+        assert_eq!((found.line_offset, found.fuzz), (0, 0));
+
+        let both = EditJson {
+            hunk: Some(" fn a() {\n".to_string()),
+            ..hunk.clone()
+        };
+        assert!(matches!(
+            resolve_in(content, &both, "any"),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+        let missing = EditJson {
+            old_text: Some("    three();\n".to_string()),
+            max_fuzz: Some(0),
+            ..hunk
+        };
+        assert!(matches!(
+            resolve_in(content, &missing, "any"),
+            Err(EditError::HunkMismatch { max_fuzz: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_regex_replace() {
+        let content = "foo(1); foo(2); foo(3);";
+        let json = r#"{"kind": "regex_replace", "pattern": "foo\\((?P<n>\\d)\\)", "replacement": "bar(${n}0)",
+            "max_replacements": 2, "expected_count": 3}"#;
+        let edit_json: EditJson = serde_json::from_str(json).unwrap();
+
+        let edits = resolve_in(content, &edit_json, "abc").unwrap();
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[1].replacement, "bar(20)");
+        assert_eq!(edits[1].expected_checksum, "abc");
+        assert_eq!(edits[1].expected_text.as_deref(), Some("foo(2)"));
+
+        let wrong_count = EditJson {
+            expected_count: Some(1),
+            ..edit_json
+        };
+        let error = resolve_in(content, &wrong_count, "abc").unwrap_err();
+        assert_eq!(error.candidates().len(), 3);
+        assert!(
+            error
+                .to_string()
+                .starts_with("Pattern \"foo\\\\((?P<n>\\\\d)\\\\)\" matched 3 time(s), expected 1")
+        );
+    }
+
+    #[test]
+    fn test_resolve_regex_single_span_fields() {
+        let content = "1 22";
+        let resolve = |json: &str| {
+            let edit_json: EditJson = serde_json::from_str(json).unwrap();
+            resolve_in(content, &edit_json, "abc")
+        };
+        let checksum = blake3::hash(b"1").to_hex().to_string();
+
+        let json = format!(
+            r#"{{"kind": "regex_replace", "pattern": "\\d+", "replacement": "n", "expected_span_checksum": "{}"}}"#,
+            checksum
+        );
+        assert!(matches!(resolve(&json), Err(EditError::InvalidEditSpec(_))));
+        let json = r#"{"kind": "regex_replace", "pattern": "\\d+", "replacement": "n", "context_after": " "}"#;
+        assert!(matches!(resolve(json), Err(EditError::InvalidEditSpec(_))));
+        let json = r#"{"kind": "regex_replace", "pattern": "\\d+", "replacement": "n", "max_replacements": 1, "expected_text": "1"}"#;
+        assert!(matches!(resolve(json), Err(EditError::InvalidEditSpec(_))));
+
+        // Fine with a single match
+        let json = format!(
+            r#"{{"kind": "regex_replace", "pattern": "\\d+", "replacement": "n", "max_replacements": 1, "expected_span_checksum": "{}"}}"#,
+            checksum
+        );
+        let edits = resolve(&json).unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].expected_span_checksum, Some(checksum));
+    }
 }
@@ -1,5 +1,7 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::sync::LazyLock;
 
 /// Supported programming languages for file type detection
 ///
@@ -22,11 +24,55 @@ pub enum Language {
     TypeScript,
     /// Python (.py, .pyi)
     Python,
+    /// Go (.go)
+    Go,
+    /// Ruby (.rb, .rake, .gemspec)
+    Ruby,
+    /// PHP (.php, .phtml)
+    Php,
+    /// Shell scripts (.sh, .bash, .zsh, .ksh)
+    Shell,
+    /// SQL (.sql)
+    Sql,
+    /// HTML (.html, .htm, .xhtml)
+    Html,
+    /// CSS (.css)
+    Css,
+    /// Markdown (.md, .markdown)
+    Markdown,
+    /// JSON (.json)
+    Json,
+    /// TOML (.toml)
+    Toml,
+    /// YAML (.yaml, .yml)
+    Yaml,
     /// Unknown or unsupported language
     Unknown,
 }
 
 impl Language {
+    /// Every language that can be detected (all variants but `Unknown`)
+    pub const ALL: &'static [Language] = &[
+        Language::Rust,
+        Language::C,
+        Language::Cpp,
+        Language::Java,
+        Language::JavaScript,
+        Language::TypeScript,
+        Language::Python,
+        Language::Go,
+        Language::Ruby,
+        Language::Php,
+        Language::Shell,
+        Language::Sql,
+        Language::Html,
+        Language::Css,
+        Language::Markdown,
+        Language::Json,
+        Language::Toml,
+        Language::Yaml,
+    ];
+
     /// Get the file extensions associated with this language
     ///
     /// # Returns
@@ -40,6 +86,17 @@ impl Language {
             Language::JavaScript => &["js", "mjs", "cjs"],
             Language::TypeScript => &["ts", "tsx"],
             Language::Python => &["py", "pyi"],
+            Language::Go => &["go"],
+            Language::Ruby => &["rb", "rake", "gemspec"],
+            Language::Php => &["php", "phtml"],
+            Language::Shell => &["sh", "bash", "zsh", "ksh"],
+            Language::Sql => &["sql"],
+            Language::Html => &["html", "htm", "xhtml"],
+            Language::Css => &["css"],
+            Language::Markdown => &["md", "markdown"],
+            Language::Json => &["json"],
+            Language::Toml => &["toml"],
+            Language::Yaml => &["yaml", "yml"],
             Language::Unknown => &[],
         }
     }
@@ -54,14 +111,206 @@ impl Language {
             Language::JavaScript => "JavaScript",
             Language::TypeScript => "TypeScript",
             Language::Python => "Python",
+            Language::Go => "Go",
+            Language::Ruby => "Ruby",
+            Language::Php => "PHP",
+            Language::Shell => "Shell",
+            Language::Sql => "SQL",
+            Language::Html => "HTML",
+            Language::Css => "CSS",
+            Language::Markdown => "Markdown",
+            Language::Json => "JSON",
+            Language::Toml => "TOML",
+            Language::Yaml => "YAML",
             Language::Unknown => "Unknown",
         }
     }
 
+    /// Comment markers, string delimiters and indentation of this language
+    ///
+    /// # Example
+    /// ```
+    /// use llm_transform::Language;
+    /// let syntax = Language::Python.syntax();
+    /// assert_eq!(syntax.line_comments, &["#"]);
+    /// assert_eq!(syntax.indent, "    ");
+    /// ```
+    pub fn syntax(&self) -> LanguageSyntax {
+        const C_STYLE: LanguageSyntax = LanguageSyntax {
+            line_comments: &["//"],
+            block_comment: Some(("/*", "*/")),
+            string_delimiters: &["\"", "'"],
+            indent: "    ",
+        };
+        const HASH: LanguageSyntax = LanguageSyntax {
+            line_comments: &["#"],
+            block_comment: None,
+            string_delimiters: &["\"", "'"],
+            indent: "  ",
+        };
+        match self {
+            Language::Rust => LanguageSyntax {
+                string_delimiters: &["\""],
+                ..C_STYLE
+            },
+            Language::C | Language::Cpp => C_STYLE,
+            Language::Java => LanguageSyntax {
+                string_delimiters: &["\"\"\"", "\"", "'"],
+                ..C_STYLE
+            },
+            Language::JavaScript | Language::TypeScript => LanguageSyntax {
+                string_delimiters: &["\"", "'", "`"],
+                indent: "  ",
+                ..C_STYLE
+            },
+            Language::Python => LanguageSyntax {
+                string_delimiters: &["\"\"\"", "'''", "\"", "'"],
+                indent: "    ",
+                ..HASH
+            },
+            Language::Go => LanguageSyntax {
+                string_delimiters: &["\"", "`", "'"],
+                indent: "\t",
+                ..C_STYLE
+            },
+            Language::Ruby => LanguageSyntax {
+                block_comment: Some(("=begin", "=end")),
+                ..HASH
+            },
+            Language::Php => LanguageSyntax {
+                line_comments: &["//", "#"],
+                ..C_STYLE
+            },
+            Language::Shell => LanguageSyntax {
+                string_delimiters: &["\"", "'", "`"],
+                ..HASH
+            },
+            Language::Sql => LanguageSyntax {
+                line_comments: &["--"],
+                block_comment: Some(("/*", "*/")),
+                string_delimiters: &["'", "\""],
+                indent: "  ",
+            },
+            Language::Html => LanguageSyntax {
+                line_comments: &[],
+                block_comment: Some(("<!--", "-->")),
+                string_delimiters: &["\"", "'"],
+                indent: "  ",
+            },
+            // Prose: apostrophes and quotes don't open anything
+            Language::Markdown => LanguageSyntax {
+                line_comments: &[],
+                block_comment: Some(("<!--", "-->")),
+                string_delimiters: &[],
+                indent: "  ",
+            },
+            Language::Css => LanguageSyntax {
+                line_comments: &[],
+                indent: "  ",
+                ..C_STYLE
+            },
+            Language::Json => LanguageSyntax {
+                line_comments: &[],
+                block_comment: None,
+                string_delimiters: &["\""],
+                indent: "  ",
+            },
+            Language::Toml => LanguageSyntax {
+                string_delimiters: &["\"\"\"", "'''", "\"", "'"],
+                indent: "",
+                ..HASH
+            },
+            Language::Yaml => HASH,
+            Language::Unknown => LanguageSyntax {
+                line_comments: &[],
+                block_comment: None,
+                string_delimiters: &[],
+                indent: "    ",
+            },
+        }
+    }
+
     /// Check if this language is supported for editing
     pub fn is_supported(&self) -> bool {
         !matches!(self, Language::Unknown)
     }
+
+    /// Check if a tree-sitter grammar is bundled for this language, as
+    /// symbol edits, `outline` and syntax validation need
+    pub fn has_grammar(&self) -> bool {
+        crate::syntax::grammar(*self).is_some()
+    }
+
+    /// Create an extension filter for this language
+    ///
+    /// Useful for glob patterns or file filtering operations.
+    ///
+    /// # Returns
+    /// * A comma-separated string of extensions (without dots, suitable for glob patterns)
+    ///
+    /// # Example
+    /// ```
+    /// use llm_transform::Language;
+    /// let rust_exts = Language::Rust.extension_filter();
+    /// assert_eq!(rust_exts, "rs");
+    /// ```
+    pub fn extension_filter(&self) -> String {
+        self.extensions().join(",")
+    }
+
+    /// Language for a name as used by editors, modelines and interpreters
+    ///
+    /// Case-insensitive; accepts vim file types (`cpp`, `python`), emacs
+    /// modes (`c++`, `js`) and interpreter names (`python3`, `node`).
+    ///
+    /// # Example
+    /// ```
+    /// use llm_transform::Language;
+    /// assert_eq!(Language::from_name("C++"), Some(Language::Cpp));
+    /// assert_eq!(Language::from_name("python3"), Some(Language::Python));
+    /// assert_eq!(Language::from_name("cobol"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Language> {
+        let name = name.to_lowercase();
+        // Interpreter versions: python3, python3.12, pypy3
+        let name = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+        match name {
+            "rust" | "rs" | "rust-script" => Some(Language::Rust),
+            "c" => Some(Language::C),
+            "cpp" | "c++" | "cxx" | "cc" => Some(Language::Cpp),
+            "java" => Some(Language::Java),
+            "javascript" | "js" | "js2" | "node" | "nodejs" | "bun" => Some(Language::JavaScript),
+            "typescript" | "ts" | "typescriptreact" | "tsx" | "ts-node" | "deno" => {
+                Some(Language::TypeScript)
+            }
+            "python" | "py" | "pypy" => Some(Language::Python),
+            "go" | "golang" => Some(Language::Go),
+            "ruby" | "rb" | "jruby" => Some(Language::Ruby),
+            "php" => Some(Language::Php),
+            "sh" | "shell" | "bash" | "zsh" | "ksh" | "dash" | "ash" => Some(Language::Shell),
+            "sql" | "mysql" | "plsql" | "pgsql" => Some(Language::Sql),
+            "html" | "xhtml" => Some(Language::Html),
+            "css" => Some(Language::Css),
+            "markdown" | "md" => Some(Language::Markdown),
+            "json" => Some(Language::Json),
+            "toml" => Some(Language::Toml),
+            "yaml" | "yml" => Some(Language::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// Lexical conventions of a [`Language`], from [`Language::syntax`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LanguageSyntax {
+    /// Markers that start a comment running to the end of the line
+    pub line_comments: &'static [&'static str],
+    /// Opening and closing markers of a block comment, if the language has one
+    pub block_comment: Option<(&'static str, &'static str)>,
+    /// Quotes that open and close a string (or character) literal, longest first
+    pub string_delimiters: &'static [&'static str],
+    /// Conventional indentation unit (`""` if nesting is not indented, as in TOML)
+    pub indent: &'static str,
 }
 
 impl std::fmt::Display for Language {
@@ -99,40 +348,481 @@ pub fn detect_language<P: AsRef<Path>>(path: P) -> Language {
         .and_then(|ext| ext.to_str())
         .unwrap_or("");
 
-    // Map extension to language
-    match extension {
-        "rs" => Language::Rust,
-        "c" => Language::C,
-        "h" => Language::C,
-        "cpp" | "cc" | "cxx" => Language::Cpp,
-        "hpp" | "hxx" | "hh" => Language::Cpp,
-        "java" => Language::Java,
-        "js" | "mjs" | "cjs" => Language::JavaScript,
-        "ts" | "tsx" => Language::TypeScript,
-        "py" | "pyi" => Language::Python,
-        _ => Language::Unknown,
-    }
+    // The language listing the extension (`.h` is C's; content detection
+    // tells C++ headers apart)
+    Language::ALL
+        .iter()
+        .copied()
+        .find(|language| language.extensions().contains(&extension))
+        .unwrap_or(Language::Unknown)
+}
+
+/// Result of [`detect_language_from_content`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageDetection {
+    /// The detected language (`Unknown` if nothing matched, or if the file is
+    /// recognized as something unsupported, like an awk script)
+    pub language: Language,
+    /// How sure the detection is, from 0.0 (a guess) to 1.0
+    pub confidence: f32,
+    /// Which signal decided the language
+    pub source: DetectionSource,
+    /// Why this language was chosen, e.g. `shebang "#!/usr/bin/env python3"`
+    pub reason: String,
 }
 
-/// Create an extension filter for the given language
+/// Signal that decided a [`LanguageDetection`], strongest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectionSource {
+    /// A vim or emacs modeline naming the file type
+    Modeline,
+    /// The interpreter on a `#!` first line
+    Shebang,
+    /// A well-known file name such as `SConstruct` or `Gemfile`
+    FileName,
+    /// The file extension
+    Extension,
+    /// Characteristic keywords and constructs in the content
+    Content,
+    /// Nothing matched
+    None,
+}
+
+/// A modeline is the author naming the file type outright
+const MODELINE_CONFIDENCE: f32 = 0.95;
+/// The interpreter a script runs with; wrappers and polyglots are rare
+const SHEBANG_CONFIDENCE: f32 = 0.9;
+/// Build files such as `SConstruct` are always in one language
+const FILE_NAME_CONFIDENCE: f32 = 0.9;
+/// Extensions are conventional, but files are sometimes misnamed
+const EXTENSION_CONFIDENCE: f32 = 0.8;
+/// A `.h` header with C++ constructs, which C never has outside comments
+const CPP_HEADER_CONFIDENCE: f32 = 0.7;
+/// A `.h` header without C++ constructs, which may still be included from C++
+const C_HEADER_CONFIDENCE: f32 = 0.6;
+/// Several constructs of one language and fewer of any other
+const CONTENT_CONFIDENCE: f32 = 0.5;
+/// A single construct, which another language may share
+const WEAK_CONTENT_CONFIDENCE: f32 = 0.3;
+
+/// Lines at each end of a file searched for modelines (as vim does)
+const MODELINE_LINES: usize = 5;
+
+/// Lines of content scanned by the heuristics
+const HEURISTIC_LINES: usize = 200;
+
+/// Well-known file names without a telling extension
+const FILE_NAMES: &[(&str, Language)] = &[
+    ("SConstruct", Language::Python),
+    ("SConscript", Language::Python),
+    ("Snakefile", Language::Python),
+    ("wscript", Language::Python),
+    ("Jakefile", Language::JavaScript),
+    ("Gemfile", Language::Ruby),
+    ("Rakefile", Language::Ruby),
+    ("Cargo.lock", Language::Toml),
+    (".bashrc", Language::Shell),
+    (".bash_profile", Language::Shell),
+    (".zshrc", Language::Shell),
+    (".profile", Language::Shell),
+];
+
+/// Detect the language of a file from its path and content
 ///
-/// Useful for glob patterns or file filtering operations.
+/// Signals are tried from the most to the least explicit: a vim or emacs
+/// modeline, a `#!` interpreter line, a well-known file name, the file
+/// extension, and finally characteristic constructs in the content. A `.h`
+/// header is checked for C++ constructs, since the extension alone cannot
+/// tell C from C++.
 ///
 /// # Arguments
-/// * `language` - The language to get extensions for
+/// * `path` - A path-like object (file path or just filename)
+/// * `content` - The file content
 ///
 /// # Returns
-/// * A comma-separated string of extensions (without dots, suitable for glob patterns)
+/// * The detected language, with a confidence and the reason it was chosen
 ///
-/// # Example
+/// # Examples
 /// ```
-/// use llm_transform::Language;
-/// let rust_exts = Language::Rust.extension_filter();
-/// assert_eq!(rust_exts, "rs");
+/// use llm_transform::{DetectionSource, Language, detect_language_from_content};
+/// let detection = detect_language_from_content("tool", "#!/usr/bin/env python3\nprint('hi')\n");
+/// assert_eq!(detection.language, Language::Python);
+/// assert_eq!(detection.source, DetectionSource::Shebang);
+///
+/// let detection = detect_language_from_content("list.h", "namespace util {\nclass List;\n}\n");
+/// assert_eq!(detection.language, Language::Cpp);
 /// ```
-impl Language {
-    pub fn extension_filter(&self) -> String {
-        self.extensions().join(",")
+pub fn detect_language_from_content<P: AsRef<Path>>(path: P, content: &str) -> LanguageDetection {
+    let path = path.as_ref();
+    let detection = |language, confidence, source, reason: String| LanguageDetection {
+        language,
+        confidence,
+        source,
+        reason,
+    };
+
+    let modeline =
+        find_modeline(content).and_then(|(name, line)| Some((Language::from_name(&name)?, line)));
+    if let Some((language, line)) = modeline {
+        return detection(
+            language,
+            MODELINE_CONFIDENCE,
+            DetectionSource::Modeline,
+            format!("modeline {:?}", line),
+        );
+    }
+
+    // `#![...]` is a Rust inner attribute, not a shebang
+    let shebang = content
+        .lines()
+        .next()
+        .filter(|line| line.starts_with("#!") && !line.starts_with("#!["));
+    let mut unsupported_shebang = None;
+    if let Some(line) = shebang {
+        let interpreter = shebang_interpreter(line);
+        match Language::from_name(&interpreter) {
+            Some(language) => {
+                return detection(
+                    language,
+                    SHEBANG_CONFIDENCE,
+                    DetectionSource::Shebang,
+                    format!("shebang {:?}", line),
+                );
+            }
+            // The name or extension may still tell (e.g. a `.py` file run by a wrapper)
+            None => {
+                unsupported_shebang = Some(format!(
+                    "shebang {:?}: interpreter {:?} is not a supported language",
+                    line, interpreter
+                ));
+            }
+        }
+    }
+
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    if let Some(&(name, language)) = FILE_NAMES.iter().find(|(name, _)| *name == file_name) {
+        return detection(
+            language,
+            FILE_NAME_CONFIDENCE,
+            DetectionSource::FileName,
+            format!("well-known file name {:?}", name),
+        );
+    }
+
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    match detect_language(path) {
+        Language::C if extension == "h" => {
+            return match cpp_construct(content) {
+                Some(construct) => detection(
+                    Language::Cpp,
+                    CPP_HEADER_CONFIDENCE,
+                    DetectionSource::Content,
+                    format!("\".h\" header with C++ construct {:?}", construct),
+                ),
+                None => detection(
+                    Language::C,
+                    C_HEADER_CONFIDENCE,
+                    DetectionSource::Extension,
+                    "\".h\" header without C++ constructs".to_string(),
+                ),
+            };
+        }
+        Language::Unknown => {}
+        language => {
+            return detection(
+                language,
+                EXTENSION_CONFIDENCE,
+                DetectionSource::Extension,
+                format!("extension \".{}\"", extension),
+            );
+        }
+    }
+
+    if let Some(reason) = unsupported_shebang {
+        return detection(
+            Language::Unknown,
+            SHEBANG_CONFIDENCE,
+            DetectionSource::Shebang,
+            reason,
+        );
+    }
+
+    match guess_from_content(content) {
+        Some((language, hits)) => detection(
+            language,
+            if hits.len() > 1 {
+                CONTENT_CONFIDENCE
+            } else {
+                WEAK_CONTENT_CONFIDENCE
+            },
+            DetectionSource::Content,
+            format!(
+                "content contains {}",
+                hits.iter()
+                    .map(|hit| format!("{:?}", hit))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ),
+        None => detection(
+            Language::Unknown,
+            0.0,
+            DetectionSource::None,
+            "no extension, name or content match".to_string(),
+        ),
+    }
+}
+
+/// File type named by a vim (`vim: set ft=python:`) or emacs (`-*- mode: c++ -*-`) modeline,
+/// with the line it is on
+fn find_modeline(content: &str) -> Option<(String, &str)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let tail = lines
+        .len()
+        .saturating_sub(MODELINE_LINES)
+        .max(MODELINE_LINES.min(lines.len()));
+    lines[..MODELINE_LINES.min(lines.len())]
+        .iter()
+        .chain(&lines[tail..])
+        .find_map(|line| {
+            vim_modeline(line)
+                .or_else(|| emacs_modeline(line))
+                .map(|name| (name, line.trim()))
+        })
+}
+
+fn vim_modeline(line: &str) -> Option<String> {
+    let start = ["vim:", "vi:", "ex:"].iter().find_map(|marker| {
+        line.match_indices(marker)
+            .find(|(at, _)| *at == 0 || line[..*at].ends_with(char::is_whitespace))
+            .map(|(at, _)| at + marker.len())
+    })?;
+    line[start..]
+        .split([' ', '\t', ':'])
+        .find_map(|option| {
+            ["ft=", "filetype=", "syntax=", "syn="]
+                .iter()
+                .find_map(|key| option.strip_prefix(key))
+        })
+        .map(str::to_string)
+}
+
+fn emacs_modeline(line: &str) -> Option<String> {
+    let (_, rest) = line.split_once("-*-")?;
+    let (inner, _) = rest.split_once("-*-")?;
+    if !inner.contains(':') {
+        return Some(inner.trim().to_string());
+    }
+    inner.split(';').find_map(|setting| {
+        let (key, value) = setting.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case("mode")
+            .then(|| value.trim().to_string())
+    })
+}
+
+/// Interpreter of a `#!` line, skipping `env` and its options
+fn shebang_interpreter(line: &str) -> String {
+    let mut words = line[2..].split_whitespace();
+    let mut program = words.next().unwrap_or("");
+    if program.rsplit('/').next() == Some("env") {
+        program = words
+            .find(|word| !word.starts_with('-') && !word.contains('='))
+            .unwrap_or("");
+    }
+    program.rsplit('/').next().unwrap_or(program).to_string()
+}
+
+/// C++-only constructs, matched against a header's code with comments removed
+static CPP_CONSTRUCTS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    patterns(&[
+        r"^\s*(inline\s+)?namespace\b",
+        r"\busing\s+namespace\b",
+        r"^\s*class\s+\w+",
+        r"^\s*template\s*<",
+        r"\bstd::\w+",
+        r"^\s*(public|private|protected)\s*:",
+        // C headers all end in .h
+        r"^\s*#\s*include\s*<\w+>",
+        r"\b(constexpr|nullptr)\b",
+    ])
+});
+
+/// Characteristic constructs of each language, matched line by line
+static CONTENT_PATTERNS: LazyLock<Vec<(Language, Vec<Regex>)>> = LazyLock::new(|| {
+    vec![
+        (
+            Language::Rust,
+            patterns(&[
+                r"^\s*fn main\(\)",
+                r"^use std::",
+                r"^\s*pub fn \w+",
+                r"^\s*impl\b",
+                r"\blet mut \w+",
+                r"^\s*#\[derive\(",
+            ]),
+        ),
+        (
+            Language::Python,
+            patterns(&[
+                r"^def \w+\(",
+                r"^import \w+(\.\w+)*( as \w+)?\s*$",
+                r"^from \S+ import ",
+                r"^if __name__ == ",
+                r"\bself\.\w+",
+                r"^\s*elif\b.*:\s*$",
+            ]),
+        ),
+        (
+            Language::Java,
+            patterns(&[
+                r"^public (final |abstract )?class \w+",
+                r"^package [\w.]+;",
+                r"^import java\.",
+                r"\bpublic static void main\(",
+                r"\bSystem\.out\.",
+            ]),
+        ),
+        (
+            Language::C,
+            patterns(&[
+                r"^#include <(stdio|stdlib|string|unistd)\.h>",
+                r"^int main\(",
+                r"\bprintf\(",
+                r"\bmalloc\(",
+            ]),
+        ),
+        (
+            Language::Cpp,
+            patterns(&[
+                r"^#include <\w+>",
+                r"\bstd::\w+",
+                r"^\s*namespace \w+",
+                r"^\s*template\s*<",
+                r"\bcout <<",
+            ]),
+        ),
+        (
+            Language::JavaScript,
+            patterns(&[
+                r"^\s*(async )?function\*? ?\w+\(",
+                r#"\brequire\(['"]"#,
+                r"\bconsole\.log\(",
+                r"\bmodule\.exports\b",
+                r"=> \{",
+            ]),
+        ),
+        (
+            Language::TypeScript,
+            patterns(&[
+                r"^\s*(export )?interface \w+",
+                r"^export type ",
+                r":\s*(string|number|boolean)\b",
+                r"^import type ",
+            ]),
+        ),
+        (
+            Language::Go,
+            patterns(&[r"^package \w+\s*$", r"^func ", r"\w+ := ", r"\bfmt\.Print"]),
+        ),
+        (
+            Language::Php,
+            patterns(&[r"^<\?php", r"\$this->", r"^\s*echo "]),
+        ),
+    ]
+});
+
+fn patterns(sources: &[&str]) -> Vec<Regex> {
+    sources
+        .iter()
+        .map(|source| Regex::new(source).expect("valid pattern"))
+        .collect()
+}
+
+/// First C++-only construct in a header's code, if any
+fn cpp_construct(content: &str) -> Option<String> {
+    let code = strip_c_comments(content);
+    CPP_CONSTRUCTS
+        .iter()
+        .find_map(|pattern| code.iter().find_map(|line| pattern.find(line)))
+        .map(|found| found.as_str().trim().to_string())
+}
+
+/// The first lines of C-family `content` with `//` and `/* */` comments removed
+fn strip_c_comments(content: &str) -> Vec<String> {
+    let mut in_block = false;
+    content
+        .lines()
+        .take(HEURISTIC_LINES)
+        .map(|line| {
+            let mut code = String::new();
+            let mut rest = line;
+            loop {
+                if in_block {
+                    match rest.find("*/") {
+                        Some(end) => {
+                            rest = &rest[end + 2..];
+                            in_block = false;
+                        }
+                        None => break,
+                    }
+                } else {
+                    let line_comment = rest.find("//");
+                    let block_comment = rest.find("/*");
+                    match (line_comment, block_comment) {
+                        (Some(line_at), Some(block_at)) if line_at < block_at => {
+                            code.push_str(&rest[..line_at]);
+                            break;
+                        }
+                        (Some(line_at), None) => {
+                            code.push_str(&rest[..line_at]);
+                            break;
+                        }
+                        (_, Some(block_at)) => {
+                            code.push_str(&rest[..block_at]);
+                            code.push(' ');
+                            rest = &rest[block_at + 2..];
+                            in_block = true;
+                        }
+                        (None, None) => {
+                            code.push_str(rest);
+                            break;
+                        }
+                    }
+                }
+            }
+            code
+        })
+        .collect()
+}
+
+/// Language with the most characteristic constructs in the content, and the constructs found
+///
+/// `None` if nothing matched or two languages are tied.
+fn guess_from_content(content: &str) -> Option<(Language, Vec<String>)> {
+    let head: Vec<&str> = content.lines().take(HEURISTIC_LINES).collect();
+
+    let mut scores: Vec<(Language, Vec<String>)> = CONTENT_PATTERNS
+        .iter()
+        .map(|(language, patterns)| {
+            let hits = patterns
+                .iter()
+                .filter_map(|pattern| head.iter().find_map(|line| pattern.find(line)))
+                .map(|found| found.as_str().trim().to_string())
+                .collect();
+            (*language, hits)
+        })
+        .filter(|(_, hits): &(Language, Vec<String>)| !hits.is_empty())
+        .collect();
+    scores.sort_by_key(|(_, hits)| std::cmp::Reverse(hits.len()));
+    match scores.as_slice() {
+        [] => None,
+        [(_, best), (_, second), ..] if best.len() == second.len() => None,
+        _ => Some(scores.remove(0)),
     }
 }
 
@@ -189,6 +879,25 @@ mod tests {
         assert_eq!(detect_language("type.pyi"), Language::Python);
     }
 
+    #[test]
+    fn test_detect_scripting_languages() {
+        assert_eq!(detect_language("main.go"), Language::Go);
+        assert_eq!(detect_language("lib/tasks/db.rake"), Language::Ruby);
+        assert_eq!(detect_language("index.php"), Language::Php);
+        assert_eq!(detect_language("install.sh"), Language::Shell);
+        assert_eq!(detect_language("schema.sql"), Language::Sql);
+    }
+
+    #[test]
+    fn test_detect_markup_and_config() {
+        assert_eq!(detect_language("index.htm"), Language::Html);
+        assert_eq!(detect_language("site.css"), Language::Css);
+        assert_eq!(detect_language("README.md"), Language::Markdown);
+        assert_eq!(detect_language("package.json"), Language::Json);
+        assert_eq!(detect_language("Cargo.toml"), Language::Toml);
+        assert_eq!(detect_language("ci.yml"), Language::Yaml);
+    }
+
     #[test]
     fn test_detect_unknown() {
         assert_eq!(detect_language("unknown.xyz"), Language::Unknown);
@@ -201,12 +910,46 @@ mod tests {
     fn test_extensions() {
         assert_eq!(Language::Rust.extensions(), &["rs"]);
         assert_eq!(Language::C.extensions(), &["c", "h"]);
-        assert_eq!(Language::Cpp.extensions(), &["cpp", "cc", "cxx", "hpp", "hxx", "hh"]);
+        assert_eq!(
+            Language::Cpp.extensions(),
+            &["cpp", "cc", "cxx", "hpp", "hxx", "hh"]
+        );
         assert_eq!(Language::Java.extensions(), &["java"]);
         assert_eq!(Language::JavaScript.extensions(), &["js", "mjs", "cjs"]);
         assert_eq!(Language::TypeScript.extensions(), &["ts", "tsx"]);
         assert_eq!(Language::Python.extensions(), &["py", "pyi"]);
+        assert_eq!(Language::Go.extensions(), &["go"]);
+        assert_eq!(Language::Ruby.extensions(), &["rb", "rake", "gemspec"]);
+        assert_eq!(Language::Php.extensions(), &["php", "phtml"]);
+        assert_eq!(Language::Shell.extensions(), &["sh", "bash", "zsh", "ksh"]);
+        assert_eq!(Language::Sql.extensions(), &["sql"]);
+        assert_eq!(Language::Html.extensions(), &["html", "htm", "xhtml"]);
+        assert_eq!(Language::Css.extensions(), &["css"]);
+        assert_eq!(Language::Markdown.extensions(), &["md", "markdown"]);
+        assert_eq!(Language::Json.extensions(), &["json"]);
+        assert_eq!(Language::Toml.extensions(), &["toml"]);
+        assert_eq!(Language::Yaml.extensions(), &["yaml", "yml"]);
         assert_eq!(Language::Unknown.extensions(), &[] as &[&str]);
+
+        // Every extension detects its own language
+        for &language in Language::ALL {
+            for extension in language.extensions() {
+                assert_eq!(
+                    detect_language(format!("file.{}", extension)),
+                    language,
+                    "extension {:?}",
+                    extension
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_has_grammar() {
+        assert!(Language::Rust.has_grammar());
+        assert!(Language::Python.has_grammar());
+        assert!(!Language::Markdown.has_grammar());
+        assert!(!Language::Unknown.has_grammar());
     }
 
     #[test]
@@ -218,6 +961,7 @@ mod tests {
         assert!(Language::JavaScript.is_supported());
         assert!(Language::TypeScript.is_supported());
         assert!(Language::Python.is_supported());
+        assert!(Language::Yaml.is_supported());
         assert!(!Language::Unknown.is_supported());
     }
 
@@ -225,9 +969,30 @@ mod tests {
     fn test_display() {
         assert_eq!(Language::Rust.to_string(), "Rust");
         assert_eq!(Language::Cpp.to_string(), "C++");
+        assert_eq!(Language::Php.to_string(), "PHP");
         assert_eq!(Language::Unknown.to_string(), "Unknown");
     }
 
+    #[test]
+    fn test_syntax() {
+        assert_eq!(Language::Rust.syntax().block_comment, Some(("/*", "*/")));
+        assert_eq!(Language::Php.syntax().line_comments, &["//", "#"]);
+        assert_eq!(Language::Sql.syntax().line_comments, &["--"]);
+        assert_eq!(Language::Html.syntax().block_comment, Some(("<!--", "-->")));
+        assert_eq!(
+            Language::Markdown.syntax().string_delimiters,
+            &[] as &[&str]
+        );
+        assert_eq!(
+            Language::Shell.syntax().string_delimiters,
+            &["\"", "'", "`"]
+        );
+        assert_eq!(Language::Json.syntax().line_comments, &[] as &[&str]);
+        assert_eq!(Language::Toml.syntax().string_delimiters[0], "\"\"\"");
+        assert_eq!(Language::Go.syntax().indent, "\t");
+        assert_eq!(Language::Yaml.syntax().indent, "  ");
+    }
+
     #[test]
     fn test_extension_filter() {
         assert_eq!(Language::Rust.extension_filter(), "rs");
@@ -235,4 +1000,162 @@ mod tests {
         assert_eq!(Language::Python.extension_filter(), "py,pyi");
         assert_eq!(Language::Unknown.extension_filter(), "");
     }
+
+    #[test]
+    fn test_detect_from_shebang() {
+        let detection =
+            detect_language_from_content("bin/tool", "#!/usr/bin/env -S python3 -u\nprint(1)\n");
+        assert_eq!(
+            (detection.language, detection.source),
+            (Language::Python, DetectionSource::Shebang)
+        );
+        assert_eq!(detection.reason, "shebang \"#!/usr/bin/env -S python3 -u\"");
+
+        assert_eq!(
+            detect_language_from_content("run", "#!/usr/local/bin/node\n").language,
+            Language::JavaScript
+        );
+        assert_eq!(
+            detect_language_from_content("build", "#!/bin/bash\necho hi\n").language,
+            Language::Shell
+        );
+        let detection = detect_language_from_content("count", "#!/usr/bin/awk -f\n{ n++ }\n");
+        assert_eq!(
+            (detection.language, detection.source),
+            (Language::Unknown, DetectionSource::Shebang)
+        );
+
+        // An unknown interpreter leaves the decision to the extension
+        let detection = detect_language_from_content("x.py", "#!/opt/tools/runner\nprint(1)\n");
+        assert_eq!(
+            (detection.language, detection.source),
+            (Language::Python, DetectionSource::Extension)
+        );
+
+        // A Rust inner attribute is not a shebang
+        let detection =
+            detect_language_from_content("src/lib.rs", "#![deny(missing_docs)]\n//! Docs\n");
+        assert_eq!(
+            (detection.language, detection.source),
+            (Language::Rust, DetectionSource::Extension)
+        );
+    }
+
+    #[test]
+    fn test_detect_from_modeline() {
+        let detection = detect_language_from_content("x.txt", "// vim: set ft=cpp ts=4:\nint x;\n");
+        assert_eq!(
+            (detection.language, detection.source),
+            (Language::Cpp, DetectionSource::Modeline)
+        );
+        assert_eq!(
+            detect_language_from_content("x", "/* -*- mode: c++; tab-width: 4 -*- */\n").language,
+            Language::Cpp
+        );
+        assert_eq!(
+            detect_language_from_content("x", "# -*- python -*-\n").language,
+            Language::Python
+        );
+
+        // Modelines at the end of the file, and taking precedence over the extension
+        let content = format!("{}// vi:ft=rust\n", "x\n".repeat(20));
+        assert_eq!(
+            detect_language_from_content("x.c", &content).language,
+            Language::Rust
+        );
+        // Not in the first or last lines
+        let content = format!("{}// vim: ft=rust\n{}", "x\n".repeat(10), "x\n".repeat(10));
+        assert_eq!(
+            detect_language_from_content("x.c", &content).language,
+            Language::C
+        );
+    }
+
+    #[test]
+    fn test_detect_from_file_name() {
+        let detection = detect_language_from_content("SConstruct", "env = Environment()\n");
+        assert_eq!(
+            (detection.language, detection.source),
+            (Language::Python, DetectionSource::FileName)
+        );
+        assert_eq!(
+            detect_language_from_content("web/Jakefile", "task('default');\n").language,
+            Language::JavaScript
+        );
+
+        // Unsupported languages are not recognized by name
+        let detection = detect_language_from_content("project/Makefile", "all:\n\tcc main.c\n");
+        assert_eq!(
+            (detection.language, detection.source),
+            (Language::Unknown, DetectionSource::None)
+        );
+    }
+
+    #[test]
+    fn test_detect_header() {
+        let detection = detect_language_from_content(
+            "list.h",
+            "#pragma once\ntemplate<typename T>\nstruct List;\n",
+        );
+        assert_eq!(
+            (detection.language, detection.source),
+            (Language::Cpp, DetectionSource::Content)
+        );
+        let detection = detect_language_from_content("list.h", "#pragma once\nstruct list;\n");
+        assert_eq!(
+            (detection.language, detection.source),
+            (Language::C, DetectionSource::Extension)
+        );
+        assert!(detection.confidence < detect_language_from_content("list.c", "").confidence);
+
+        // Comments and identifiers are not C++ constructs
+        let header = "/* storage class of each entry\n   namespace of the list */\nint namespace_id; // class id\n";
+        assert_eq!(
+            detect_language_from_content("list.h", header).language,
+            Language::C
+        );
+        let detection = detect_language_from_content("list.h", "#include <vector> /* lists */\n");
+        assert_eq!(detection.language, Language::Cpp);
+        assert_eq!(
+            detection.reason,
+            "\".h\" header with C++ construct \"#include <vector>\""
+        );
+    }
+
+    #[test]
+    fn test_detect_from_content() {
+        let detection =
+            detect_language_from_content("script", "import os\n\ndef main():\n    pass\n");
+        assert_eq!(
+            (detection.language, detection.source),
+            (Language::Python, DetectionSource::Content)
+        );
+        assert_eq!(detection.confidence, 0.5);
+
+        // ES module imports are not Python imports
+        let detection = detect_language_from_content(
+            "module",
+            "import x from 'y';\nimport { z } from \"w\";\n",
+        );
+        assert_ne!(detection.language, Language::Python);
+
+        let detection = detect_language_from_content("notes", "just some text\n");
+        assert_eq!(
+            (detection.language, detection.source),
+            (Language::Unknown, DetectionSource::None)
+        );
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Language::from_name("Python3.12"), Some(Language::Python));
+        assert_eq!(Language::from_name("c++"), Some(Language::Cpp));
+        assert_eq!(
+            Language::from_name("typescriptreact"),
+            Some(Language::TypeScript)
+        );
+        assert_eq!(Language::from_name("zsh"), Some(Language::Shell));
+        assert_eq!(Language::from_name("yml"), Some(Language::Yaml));
+        assert_eq!(Language::from_name("awk"), None);
+    }
 }
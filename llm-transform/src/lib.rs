@@ -13,16 +13,52 @@ pub mod json;
 // Language detection module
 pub mod language;
 
+// Search-anchored edit module
+pub mod search;
+
+// Unified-diff hunk module
+pub mod hunk;
+
+// Unified-diff patch file module
+pub mod patch;
+
+// Unified diff output module
+pub mod diff;
+
+// Tree-sitter parsing and syntax validation module
+pub mod syntax;
+
+// Symbol addressing module
+pub mod symbol;
+
+// File outline module
+pub mod outline;
+
 // Re-exports
-pub use position::{Position, Span, byte_to_position, span_to_positions};
-pub use file::{FileContent, read_file, FileError};
+pub use diff::{DEFAULT_DIFF_CONTEXT, unified_diff};
 pub use edit::{
-    Edit, EditResult, EditError,
-    validate_edit_span, verify_checksum, apply_edit, apply_edit_to_file,
-    PerEditResult, MultiEditResult, sort_edits_descending, apply_edits,
+    ANY_CHECKSUM, ApplyOptions, Edit, EditError, EditResult, MultiEditResult, PerEditResult,
+    Relocation, apply_edit, apply_edit_to_file, apply_edits, apply_edits_with_options,
+    ceil_char_boundary, check_overlaps, floor_char_boundary, relocate_edit, snap_to_char_boundary,
+    sort_edits_descending, validate_edit_span, verify_checksum, verify_span,
 };
+pub use file::{FileContent, FileError, read_file, write_file_atomic};
+pub use hunk::{DEFAULT_MAX_FUZZ, Hunk, HunkMatch};
 pub use json::{
-    EditRequest, EditResponse, EditJson, PerEditResultJson,
-    generate_execution_id, ExecutionLogEntry, ExecutionLog,
+    EditJson, EditKind, EditRequest, EditResponse, ExecutionLog, ExecutionLogEntry, InsertMode,
+    PerEditResultJson, generate_execution_id,
+};
+pub use language::{
+    DetectionSource, Language, LanguageDetection, LanguageSyntax, detect_language,
+    detect_language_from_content,
+};
+pub use outline::{Outline, OutlineItem, outline, outline_items};
+pub use patch::{FilePatch, PatchError, is_unified_diff, parse_patch, select_file_patch};
+pub use position::{
+    LineEnding, LineIndex, Position, PositionEncoding, PositionError, Span, byte_to_position,
+    byte_to_position_with_encoding, position_to_byte, position_to_byte_with_encoding,
+    span_to_positions, span_to_positions_with_encoding,
 };
-pub use language::{Language, detect_language};
+pub use search::{Occurrence, find_occurrences, regex_edits, select_occurrence};
+pub use symbol::{Symbol, SymbolTarget, find_symbol, symbols};
+pub use syntax::{SyntaxError, syntax_errors};
@@ -1,7 +1,8 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use llm_transform::{
-    json::{EditRequest, EditResponse, PerEditResultJson, generate_execution_id},
-    read_file, Edit,
+    ANY_CHECKSUM, ApplyOptions, Edit, HunkMatch, is_unified_diff,
+    json::{EditJson, EditRequest, EditResponse, PerEditResultJson, generate_execution_id},
+    outline, parse_patch, read_file, select_file_patch, unified_diff, write_file_atomic,
 };
 use std::fs;
 use std::io::{self, Read};
@@ -11,15 +12,31 @@ use std::io::{self, Read};
 #[command(name = "llm-transform")]
 #[command(version = "0.1.0")]
 #[command(about = "Zero-corruption text edits for LLM workflows", long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// File to transform
-    #[arg(short, long)]
-    file: String,
+    #[arg(short, long, required = true)]
+    file: Option<String>,
 
     /// JSON file containing edit specifications (omit to read from stdin)
-    #[arg(short, long)]
+    #[arg(short, long, conflicts_with = "patch")]
     edits: Option<String>,
 
+    /// Unified diff to apply instead of a JSON request (stdin holding a diff is detected too)
+    #[arg(short, long)]
+    patch: Option<String>,
+
+    /// Context lines each patch hunk may ignore at either end
+    #[arg(long, default_value_t = 0)]
+    fuzz: usize,
+
+    /// Expected BLAKE3 checksum of the file for a patch (default: rely on each hunk's old lines)
+    #[arg(long)]
+    expected_checksum: Option<String>,
+
     /// Output structured JSON instead of human-readable
     #[arg(short, long)]
     json: bool,
@@ -27,30 +44,83 @@ struct Args {
     /// Write output to file instead of stdout
     #[arg(short, long)]
     output: Option<String>,
+
+    /// Include a unified diff of the changes in the output
+    #[arg(short, long)]
+    diff: bool,
+
+    /// Context lines around each change in the diff
+    #[arg(long, default_value_t = llm_transform::DEFAULT_DIFF_CONTEXT)]
+    diff_context: usize,
+
+    /// Run the whole pipeline and report the outcome without writing the file
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Include the edited content in the output
+    #[arg(long)]
+    print_content: bool,
+
+    /// Reject edits that add parse errors for the file's language
+    #[arg(long)]
+    validate_syntax: bool,
 }
 
-/// Read EditRequest from file path or stdin
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the items of a file (functions, types, impls, ...) with their spans and checksums
+    Outline {
+        /// File to outline
+        #[arg(short, long)]
+        file: String,
+
+        /// Output structured JSON instead of human-readable
+        #[arg(short, long)]
+        json: bool,
+
+        /// Write output to file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+/// Read EditRequest from `--edits`, `--patch` or stdin
 ///
-/// If `path` is Some, reads from the file at that path.
-/// If `path` is None, reads from stdin.
-fn read_edit_request(path: Option<&String>) -> Result<EditRequest, Box<dyn std::error::Error>> {
-    let json_str = if let Some(p) = path {
-        fs::read_to_string(p)?
-    } else {
-        let mut buffer = String::new();
-        io::stdin().read_to_string(&mut buffer)?;
-        buffer
+/// A unified diff (from `--patch`, or detected on stdin) becomes a request
+/// with one hunk edit per hunk for `--file`.
+fn read_edit_request(args: &Args, file: &str) -> Result<EditRequest, Box<dyn std::error::Error>> {
+    let (text, is_patch) = match (&args.edits, &args.patch) {
+        (Some(p), _) => (fs::read_to_string(p)?, false),
+        (None, Some(p)) => (fs::read_to_string(p)?, true),
+        (None, None) => {
+            let mut buffer = String::new();
+            io::stdin().read_to_string(&mut buffer)?;
+            let is_patch = is_unified_diff(&buffer);
+            (buffer, is_patch)
+        }
     };
 
-    let request: EditRequest = serde_json::from_str(&json_str)?;
+    if is_patch {
+        let file_patch = select_file_patch(parse_patch(&text)?, file)?;
+        let expected_checksum = args.expected_checksum.as_deref().unwrap_or(ANY_CHECKSUM);
+        return Ok(file_patch.to_request(file, expected_checksum, args.fuzz));
+    }
+
+    let request: EditRequest = serde_json::from_str(&text)?;
     Ok(request)
 }
 
 fn main() {
     let args = Args::parse();
+    if let Some(Command::Outline { file, json, output }) = &args.command {
+        run_outline(file, *json, output.as_ref());
+        return;
+    }
+    // Required by clap unless a subcommand is given
+    let file = args.file.clone().expect("--file is required");
 
     // Read edit request from file or stdin
-    let edit_request = match read_edit_request(args.edits.as_ref()) {
+    let edit_request = match read_edit_request(&args, &file) {
         Ok(req) => req,
         Err(e) => {
             eprintln!("Error reading edit request: {}", e);
@@ -58,6 +128,9 @@ fn main() {
         }
     };
 
+    // A dry run never writes the file (set by flag or request field)
+    let dry_run = args.dry_run || edit_request.dry_run;
+
     // Handle "auto" execution_id
     let execution_id = if edit_request.execution_id == "auto" {
         generate_execution_id()
@@ -66,97 +139,184 @@ fn main() {
     };
 
     // Read the file to transform
-    let file_content = match read_file(&args.file) {
+    let file_content = match read_file(&file) {
         Ok(content) => content,
         Err(e) => {
-            let response = EditResponse::failure(
+            let mut response = EditResponse::failure(
                 execution_id.clone(),
-                format!("Failed to read file '{}': {}", args.file, e),
+                format!("Failed to read file '{}': {}", file, e),
             );
+            response.dry_run = dry_run;
             output_response(&response, args.json, args.output.as_ref());
             std::process::exit(1);
         }
     };
 
-    // Verify checksum matches
-    if file_content.checksum != edit_request.expected_checksum {
-        let response = EditResponse::failure(
+    // Verify checksum matches ("any" and relocation defer to per-edit span checks)
+    if edit_request.expected_checksum != ANY_CHECKSUM
+        && !edit_request.relocate
+        && file_content.checksum != edit_request.expected_checksum
+    {
+        let mut response = EditResponse::failure(
             execution_id.clone(),
             format!(
                 "Checksum mismatch: expected {}, got {}",
                 edit_request.expected_checksum, file_content.checksum
             ),
         );
+        response.dry_run = dry_run;
         output_response(&response, args.json, args.output.as_ref());
         std::process::exit(1);
     }
 
-    // Convert EditJson to Edit
-    let edits: Vec<Edit> = edit_request
+    // Resolve EditJson (byte, line/column, search or regex addresses) to Edit
+    let resolved: Vec<_> = edit_request
         .edits
-        .into_iter()
-        .map(|e| Edit {
-            byte_start: e.byte_start,
-            byte_end: e.byte_end,
-            replacement: e.replacement,
-            expected_checksum: edit_request.expected_checksum.clone(),
+        .iter()
+        .map(|e| {
+            e.resolve_with_hunk_match(
+                &file_content.content,
+                &file_content.line_index,
+                &edit_request.expected_checksum,
+                edit_request.position_encoding,
+                file_content.language,
+            )
         })
         .collect();
 
+    let failed: Vec<String> = resolved
+        .iter()
+        .enumerate()
+        .filter_map(|(i, r)| r.as_ref().err().map(|e| format!("Edit {}: {}", i, e)))
+        .collect();
+    if !failed.is_empty() {
+        let mut response = EditResponse::failure(execution_id.clone(), failed.join("; "));
+        response.edits = resolved
+            .iter()
+            .zip(&edit_request.edits)
+            .map(|(r, e)| match r {
+                Ok((edits, _)) => {
+                    PerEditResultJson::not_attempted(edits.first().map(|edit| edit.byte_start))
+                }
+                Err(err) => PerEditResultJson::unresolved(e.byte_start, err),
+            })
+            .collect();
+        response.error_count = failed.len();
+        response.skipped_count = response.edits.len() - failed.len();
+        response.dry_run = dry_run;
+        output_response(&response, args.json, args.output.as_ref());
+        std::process::exit(1);
+    }
+    // Flatten, keeping where each hunk was found to report its line offset and fuzz
+    let mut edits: Vec<Edit> = Vec::new();
+    let mut hunk_matches: Vec<Option<HunkMatch>> = Vec::new();
+    for (resolved_edits, hunk_match) in resolved.into_iter().flatten() {
+        hunk_matches.extend(std::iter::repeat_n(hunk_match, resolved_edits.len()));
+        edits.extend(resolved_edits);
+    }
+
+    let options = ApplyOptions::default()
+        .with_merge_duplicates(edit_request.merge_duplicates)
+        // The CLI never writes a partial batch, so every edit is checked and reported
+        .with_transactional(true)
+        .with_intermediate_checksums(edit_request.intermediate_checksums)
+        .with_snap_to_char_boundary(edit_request.snap_to_char_boundary)
+        .with_relocate(edit_request.relocate, edit_request.max_drift)
+        .with_validate_syntax(
+            (args.validate_syntax || edit_request.validate_syntax).then_some(file_content.language),
+        );
+
     // Apply edits
-    let result = llm_transform::apply_edits(
+    let result = llm_transform::apply_edits_with_options(
         &file_content.content,
-        &file_content.checksum,
+        &edit_request.expected_checksum,
         &edits,
+        &options,
     );
 
     // Build response
     let response = match result {
         Ok(multi_result) => {
-            let per_edit_results: Vec<PerEditResultJson> = multi_result
+            let failed = multi_result.error_count > 0;
+
+            // The batch is transactional, so every edit has a result; report them in request order
+            let mut per_edit_results = vec![PerEditResultJson::default(); edits.len()];
+            for (result, &index) in multi_result
                 .edits
                 .into_iter()
-                .map(|r| match r {
-                    llm_transform::PerEditResult::Applied { byte_offset, new_checksum, byte_shift } => {
-                        PerEditResultJson {
-                            byte_offset,
-                            status: "applied".to_string(),
-                            new_checksum: Some(new_checksum),
-                            byte_shift: Some(byte_shift),
-                            reason: None,
-                        }
-                    }
-                    llm_transform::PerEditResult::Skipped { byte_offset, reason } => {
-                        PerEditResultJson {
-                            byte_offset,
-                            status: "skipped".to_string(),
-                            new_checksum: None,
-                            byte_shift: None,
-                            reason: Some(reason),
-                        }
-                    }
-                    llm_transform::PerEditResult::Error { byte_offset, error } => {
-                        PerEditResultJson {
-                            byte_offset,
-                            status: "error".to_string(),
-                            new_checksum: None,
-                            byte_shift: None,
-                            reason: Some(error),
-                        }
-                    }
-                })
-                .collect();
+                .zip(&multi_result.edit_indices)
+            {
+                per_edit_results[index] = PerEditResultJson::from(result);
+            }
 
-            EditResponse::success(
-                execution_id,
-                multi_result.final_checksum,
+            let mut response = EditResponse::success(
+                execution_id.clone(),
+                multi_result.final_checksum.clone(),
                 multi_result.total_byte_shift,
                 multi_result.applied_count,
                 multi_result.skipped_count,
                 per_edit_results,
             )
+            .with_hunk_matches(&hunk_matches);
+            response.error_count = multi_result.error_count;
+            response.rolled_back_count = multi_result.rolled_back_count;
+
+            if failed {
+                response.success = false;
+                response.error = Some(format!(
+                    "Transaction rolled back: {} edit(s) failed; file '{}' left unchanged",
+                    multi_result.error_count, file
+                ));
+            } else {
+                if args.diff || dry_run {
+                    response.diff = Some(unified_diff(
+                        &file_content.content,
+                        &multi_result.final_content,
+                        &file,
+                        args.diff_context,
+                    ));
+                }
+                if args.print_content {
+                    response.final_content = Some(multi_result.final_content.clone());
+                }
+
+                let changed = multi_result.final_checksum != file_content.checksum;
+                let undo = EditRequest::new(
+                    file.clone(),
+                    multi_result.final_checksum.clone(),
+                    multi_result.undo.iter().map(EditJson::from).collect(),
+                );
+                if dry_run {
+                    // Report the undo request the real run would return, but leave the file alone
+                    response.undo = changed.then_some(undo);
+                } else if changed {
+                    // Write back atomically and verify what landed on disk
+                    if let Err(e) = write_file_atomic(
+                        &file,
+                        &multi_result.final_content,
+                        &multi_result.final_checksum,
+                    ) {
+                        response = EditResponse::failure(
+                            execution_id,
+                            format!("Failed to write file '{}': {}", file, e),
+                        );
+                    } else {
+                        response.undo = Some(undo);
+                    }
+                }
+            }
+
+            response.dry_run = dry_run;
+            response
+        }
+        Err(e) => {
+            let mut response =
+                EditResponse::failure(execution_id, format!("Failed to apply edits: {}", e));
+            let syntax_errors = e.syntax_errors();
+            response.syntax_errors = (!syntax_errors.is_empty()).then(|| syntax_errors.to_vec());
+            response.dry_run = dry_run;
+            response
         }
-        Err(e) => EditResponse::failure(execution_id, format!("Failed to apply edits: {}", e)),
     };
 
     // Output response
@@ -168,28 +328,100 @@ fn main() {
     }
 }
 
+/// Print the outline of `file`
+fn run_outline(file: &str, json_mode: bool, output_path: Option<&String>) {
+    let result = read_file(file)
+        .map_err(|e| format!("Failed to read file '{}': {}", file, e))
+        .and_then(|content| {
+            outline(&content).map_err(|e| format!("Failed to outline '{}': {}", file, e))
+        });
+
+    let outline = match result {
+        Ok(outline) => outline,
+        Err(error) => {
+            let output = if json_mode {
+                serde_json::json!({ "error": error }).to_string()
+            } else {
+                format!("Error: {}", error)
+            };
+            write_output(&output, output_path);
+            std::process::exit(1);
+        }
+    };
+
+    let output = if json_mode {
+        serde_json::to_string_pretty(&outline)
+            .unwrap_or_else(|_| r#"{"error": "Failed to serialize outline"}"#.to_string())
+    } else {
+        let mut output = format!(
+            "{} ({}): {} item(s)\nChecksum: {}",
+            outline.file_path,
+            outline.language,
+            outline.items.len(),
+            outline.expected_checksum
+        );
+        for item in &outline.items {
+            output.push_str(&format!(
+                "\n{}{} {}  lines {}-{}  bytes {}..{}  {}",
+                "    ".repeat(item.depth),
+                item.symbol_kind,
+                item.name,
+                item.first_line,
+                item.last_line,
+                item.byte_start,
+                item.byte_end,
+                item.expected_span_checksum
+            ));
+        }
+        output
+    };
+    write_output(&output, output_path);
+}
+
 /// Format and output the response
 fn output_response(response: &EditResponse, json_mode: bool, output_path: Option<&String>) {
     let output = if json_mode {
         // JSON output
-        serde_json::to_string_pretty(response).unwrap_or_else(|_| {
-            r#"{"error": "Failed to serialize response"}"#.to_string()
-        })
+        serde_json::to_string_pretty(response)
+            .unwrap_or_else(|_| r#"{"error": "Failed to serialize response"}"#.to_string())
     } else {
         // Human-readable output
         if response.success {
+            let mut output = format!(
+                "{} {} edit(s)\nFinal checksum: {}\nTotal byte shift: {}",
+                if response.dry_run {
+                    "Dry run: would apply"
+                } else {
+                    "Applied"
+                },
+                response.applied_count,
+                response.final_checksum,
+                response.total_byte_shift
+            );
+            if let Some(diff) = response.diff.as_deref().filter(|diff| !diff.is_empty()) {
+                output.push_str("\n\n");
+                output.push_str(diff.trim_end_matches('\n'));
+            }
+            if let Some(content) = response.final_content.as_deref() {
+                output.push_str("\n\n");
+                output.push_str(content);
+            }
+            output
+        } else {
             format!(
-                "Applied {} edit(s)\nFinal checksum: {}\nTotal byte shift: {}",
-                response.applied_count, response.final_checksum, response.total_byte_shift
+                "Error: {}",
+                response.error.as_deref().unwrap_or("Unknown error")
             )
-        } else {
-            format!("Error: {}", response.error.as_deref().unwrap_or("Unknown error"))
         }
     };
 
-    // Write to file or stdout
+    write_output(&output, output_path);
+}
+
+/// Write output to `output_path`, or to stdout
+fn write_output(output: &str, output_path: Option<&String>) {
     if let Some(path) = output_path {
-        if let Err(e) = fs::write(path, &output) {
+        if let Err(e) = fs::write(path, output) {
             eprintln!("Failed to write output to '{}': {}", path, e);
             std::process::exit(1);
         }
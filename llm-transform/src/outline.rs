@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+
+use crate::edit::EditError;
+use crate::file::FileContent;
+use crate::language::Language;
+use crate::position::Span;
+use crate::symbol::symbols;
+
+/// Items of a source file, with what an agent needs to edit them
+///
+/// `file_path` and `expected_checksum` are the fields of an
+/// [`EditRequest`](crate::EditRequest) of the same name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outline {
+    /// Path of the file
+    pub file_path: String,
+    /// Language the file was parsed as
+    pub language: Language,
+    /// BLAKE3 checksum of the whole file
+    pub expected_checksum: String,
+    /// Items in file order, enclosing items before the items inside them
+    pub items: Vec<OutlineItem>,
+}
+
+/// One item of an [`Outline`]
+///
+/// `byte_start`, `byte_end` and `expected_span_checksum` are the fields of
+/// an [`EditJson`](crate::EditJson) of the same name: an item plus a
+/// `replacement` is an edit that replaces the whole item, and fails if the
+/// item changed since the outline was taken. The item's kind is
+/// `symbol_kind` (not `kind`, which is the edit kind).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutlineItem {
+    /// Item kind, as the language spells it ("fn", "impl", "class", "def", ...)
+    pub symbol_kind: String,
+    /// Item name
+    pub name: String,
+    /// Qualified path, usable as the `symbol` of a symbol edit
+    pub path: String,
+    /// Number of enclosing items (0 at the top level)
+    pub depth: usize,
+    /// Byte offset where the item starts
+    pub byte_start: usize,
+    /// Byte offset where the item ends (exclusive)
+    pub byte_end: usize,
+    /// First line of the item (1-indexed)
+    pub first_line: usize,
+    /// Last line of the item (1-indexed)
+    pub last_line: usize,
+    /// BLAKE3 checksum of the item's bytes
+    pub expected_span_checksum: String,
+    /// Span of the item's body, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Span>,
+}
+
+/// Outline of a file read with [`read_file`](crate::read_file)
+///
+/// # Returns
+/// * `Ok(Outline)` - The items of the file
+/// * `Err(EditError::InvalidEditSpec)` - There is no grammar for the file's language
+pub fn outline(file: &FileContent) -> Result<Outline, EditError> {
+    Ok(Outline {
+        file_path: file.path.clone(),
+        language: file.language,
+        expected_checksum: file.checksum.clone(),
+        items: outline_items(&file.content, file.language)?,
+    })
+}
+
+/// Functions, types, modules and other named items in `content`
+///
+/// # Arguments
+/// * `content` - Source code
+/// * `language` - Language of `content`, which selects the grammar
+///
+/// # Returns
+/// * `Ok(Vec<OutlineItem>)` - The items, in file order
+/// * `Err(EditError::InvalidEditSpec)` - There is no grammar for `language`
+pub fn outline_items(content: &str, language: Language) -> Result<Vec<OutlineItem>, EditError> {
+    Ok(symbols(content, language)?
+        .into_iter()
+        .map(|symbol| OutlineItem {
+            expected_span_checksum: blake3::hash(
+                &content.as_bytes()[symbol.span.byte_start..symbol.span.byte_end],
+            )
+            .to_hex()
+            .to_string(),
+            symbol_kind: symbol.kind,
+            name: symbol.name,
+            path: symbol.path,
+            depth: symbol.depth,
+            byte_start: symbol.span.byte_start,
+            byte_end: symbol.span.byte_end,
+            first_line: symbol.line_start,
+            last_line: symbol.line_end,
+            body: symbol.body,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::EditJson;
+    use crate::position::{LineIndex, PositionEncoding};
+
+    #[test]
+    fn test_outline_items() {
+        let content = "struct Foo;\n\nimpl Foo {\n    fn bar(&self) {}\n}\n";
+        let items = outline_items(content, Language::Rust).unwrap();
+
+        let summary: Vec<(&str, &str, usize, usize, usize)> = items
+            .iter()
+            .map(|item| {
+                (
+                    item.symbol_kind.as_str(),
+                    item.name.as_str(),
+                    item.depth,
+                    item.first_line,
+                    item.last_line,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("struct", "Foo", 0, 1, 1),
+                ("impl", "Foo", 0, 3, 5),
+                ("fn", "bar", 1, 4, 4)
+            ]
+        );
+        assert_eq!(
+            &content[items[2].byte_start..items[2].byte_end],
+            "fn bar(&self) {}"
+        );
+        assert_eq!(
+            items[2].expected_span_checksum,
+            blake3::hash(b"fn bar(&self) {}").to_hex().to_string()
+        );
+
+        assert!(matches!(
+            outline_items("x", Language::Unknown),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+    }
+
+    #[test]
+    fn test_outline_item_is_an_edit() {
+        let content = "def a():\n    pass\n";
+        let item = &outline_items(content, Language::Python).unwrap()[0];
+
+        let mut json = serde_json::to_value(item).unwrap();
+        json["replacement"] = "def a():\n    return 1".into();
+        let edit_json: EditJson = serde_json::from_value(json).unwrap();
+        let edit = edit_json
+            .resolve(
+                content,
+                &LineIndex::new(content),
+                "any",
+                PositionEncoding::Utf8,
+                Language::Python,
+            )
+            .unwrap()
+            .remove(0);
+        assert_eq!((edit.byte_start, edit.byte_end), (0, 17));
+        assert_eq!(
+            edit.expected_span_checksum,
+            Some(item.expected_span_checksum.clone())
+        );
+    }
+}
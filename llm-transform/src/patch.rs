@@ -0,0 +1,318 @@
+use std::path::Path;
+
+use crate::json::{EditJson, EditKind, EditRequest};
+
+/// Changes to one file in a unified diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePatch {
+    /// Path on the `---` line (without an `a/` prefix), if present
+    pub old_path: Option<String>,
+    /// Path on the `+++` line (without a `b/` prefix), if present
+    pub new_path: Option<String>,
+    /// Hunks in order, each with its `@@` header line
+    pub hunks: Vec<String>,
+}
+
+impl FilePatch {
+    /// Whether this patch is for `path` (compared by trailing path components)
+    pub fn targets<P: AsRef<Path>>(&self, path: P) -> bool {
+        let path = path.as_ref();
+        [&self.new_path, &self.old_path]
+            .into_iter()
+            .flatten()
+            .any(|patch_path| path.ends_with(patch_path) || Path::new(patch_path).ends_with(path))
+    }
+
+    /// Turn every hunk into a `hunk` edit of an [`EditRequest`] for `file_path`
+    ///
+    /// # Arguments
+    /// * `file_path` - File the request edits
+    /// * `expected_checksum` - Whole-file checksum, or "any" to rely on each hunk's old lines
+    /// * `max_fuzz` - Context lines each hunk may ignore at either end
+    pub fn to_request(
+        &self,
+        file_path: &str,
+        expected_checksum: &str,
+        max_fuzz: usize,
+    ) -> EditRequest {
+        let edits = self
+            .hunks
+            .iter()
+            .map(|hunk| EditJson {
+                kind: EditKind::Hunk,
+                hunk: Some(hunk.clone()),
+                max_fuzz: Some(max_fuzz),
+                ..Default::default()
+            })
+            .collect();
+        EditRequest::new(file_path, expected_checksum, edits)
+    }
+}
+
+/// Error reading a unified diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// A line of the diff could not be parsed
+    Malformed {
+        /// Line in the diff (1-indexed)
+        line: usize,
+        message: String,
+    },
+    /// The diff contains no hunks
+    NoHunks,
+    /// The diff changes several files, none of which is the target
+    FileNotInPatch {
+        file: String,
+        /// Paths the diff does change
+        paths: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for PatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchError::Malformed { line, message } => {
+                write!(f, "Malformed patch at line {}: {}", line, message)
+            }
+            PatchError::NoHunks => write!(f, "Patch contains no hunks"),
+            PatchError::FileNotInPatch { file, paths } => {
+                write!(
+                    f,
+                    "Patch does not change '{}' (it changes: {})",
+                    file,
+                    paths.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// Whether `text` looks like a unified diff rather than a JSON request
+pub fn is_unified_diff(text: &str) -> bool {
+    !text.trim_start().starts_with('{') && text.lines().any(|line| line.starts_with("@@ -"))
+}
+
+/// Parse a unified diff into per-file patches
+///
+/// Text outside of file headers and hunks (`diff --git`, `index`, commit
+/// messages) is ignored. Hunk bodies are delimited by the line counts in
+/// their `@@ -l,s +l,s @@` headers.
+///
+/// # Returns
+/// * `Ok(Vec<FilePatch>)` - One entry per changed file, in diff order
+/// * `Err(PatchError)` - A malformed hunk header or body, or no hunks at all
+pub fn parse_patch(text: &str) -> Result<Vec<FilePatch>, PatchError> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut patches: Vec<FilePatch> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if line.starts_with("--- ")
+            && lines
+                .get(i + 1)
+                .is_some_and(|next| next.starts_with("+++ "))
+        {
+            patches.push(FilePatch {
+                old_path: header_path(line, "--- ", "a/"),
+                new_path: header_path(lines[i + 1], "+++ ", "b/"),
+                hunks: Vec::new(),
+            });
+            i += 2;
+        } else if line.starts_with("@@ ") {
+            let header_line = i + 1;
+            let malformed = |message: String| PatchError::Malformed {
+                line: header_line,
+                message,
+            };
+            let (mut old_left, mut new_left) = hunk_lengths(line)
+                .ok_or_else(|| malformed(format!("invalid hunk header {:?}", line.trim_end())))?;
+            if patches.is_empty() {
+                // A bare hunk without file headers
+                patches.push(FilePatch {
+                    old_path: None,
+                    new_path: None,
+                    hunks: Vec::new(),
+                });
+            }
+
+            let start = i;
+            i += 1;
+            while old_left > 0
+                || new_left > 0
+                || lines.get(i).is_some_and(|next| next.starts_with('\\'))
+            {
+                let Some(body) = lines.get(i) else {
+                    return Err(malformed("hunk ends early".to_string()));
+                };
+                match body.as_bytes().first() {
+                    Some(b' ') | Some(b'\n') | Some(b'\r') if old_left > 0 && new_left > 0 => {
+                        old_left -= 1;
+                        new_left -= 1;
+                    }
+                    Some(b'-') if old_left > 0 => old_left -= 1,
+                    Some(b'+') if new_left > 0 => new_left -= 1,
+                    Some(b'\\') => {}
+                    _ => {
+                        return Err(PatchError::Malformed {
+                            line: i + 1,
+                            message: format!("unexpected line {:?} in hunk", body.trim_end()),
+                        });
+                    }
+                }
+                i += 1;
+            }
+            if let Some(patch) = patches.last_mut() {
+                patch.hunks.push(lines[start..i].concat());
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    if patches.iter().all(|patch| patch.hunks.is_empty()) {
+        return Err(PatchError::NoHunks);
+    }
+    Ok(patches)
+}
+
+/// Select the patch for `file` from a parsed diff
+///
+/// A diff for a single file applies to `file` whatever its headers say, the
+/// way `patch <file>` does. Otherwise the file is matched against the
+/// header paths.
+pub fn select_file_patch<P: AsRef<Path>>(
+    patches: Vec<FilePatch>,
+    file: P,
+) -> Result<FilePatch, PatchError> {
+    let mut patches: Vec<FilePatch> = patches
+        .into_iter()
+        .filter(|patch| !patch.hunks.is_empty())
+        .collect();
+    if patches.len() == 1 {
+        return Ok(patches.remove(0));
+    }
+    match patches.iter().position(|patch| patch.targets(&file)) {
+        Some(index) => Ok(patches.remove(index)),
+        None => Err(PatchError::FileNotInPatch {
+            file: file.as_ref().display().to_string(),
+            paths: patches
+                .iter()
+                .filter_map(|patch| patch.new_path.clone().or_else(|| patch.old_path.clone()))
+                .collect(),
+        }),
+    }
+}
+
+/// Path from a `---` / `+++` line, without the `a/` / `b/` prefix or a trailing timestamp
+fn header_path(line: &str, marker: &str, prefix: &str) -> Option<String> {
+    let path = line[marker.len()..].trim_end_matches(['\n', '\r']);
+    let path = path.split('\t').next().unwrap_or(path);
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(path.strip_prefix(prefix).unwrap_or(path).to_string())
+}
+
+/// Old and new line counts from a `@@ -l,s +l,s @@` header (a missing count is 1)
+fn hunk_lengths(header: &str) -> Option<(usize, usize)> {
+    let mut ranges = header.trim_start_matches('@').split_whitespace();
+    let count = |range: &str| -> Option<usize> {
+        match range.split_once(',') {
+            Some((start, count)) => {
+                start.parse::<usize>().ok()?;
+                count.parse().ok()
+            }
+            None => range.parse::<usize>().ok().map(|_| 1),
+        }
+    };
+    let old = count(ranges.next()?.strip_prefix('-')?)?;
+    let new = count(ranges.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = concat!(
+        "diff --git a/src/lib.rs b/src/lib.rs\n",
+        "index 1111111..2222222 100644\n",
+        "--- a/src/lib.rs\n",
+        "+++ b/src/lib.rs\n",
+        "@@ -1,3 +1,3 @@\n",
+        " fn a() {\n",
+        "--- removed\n",
+        "+++ added\n",
+        " }\n",
+        "@@ -10 +10,2 @@ fn b() {\n",
+        " x\n",
+        "+y\n",
+        "\\ No newline at end of file\n",
+        "--- a/README.md\n",
+        "+++ b/README.md\n",
+        "@@ -1,1 +1,1 @@\n",
+        "-old\n",
+        "+new\n",
+    );
+
+    #[test]
+    fn test_parse_patch() {
+        assert!(is_unified_diff(DIFF));
+        assert!(!is_unified_diff(
+            "{\"edits\": [{\"hunk\": \"\\n@@ -1 +1 @@\"}]}"
+        ));
+
+        let patches = parse_patch(DIFF).unwrap();
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].new_path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(patches[0].hunks.len(), 2);
+        // Lines starting with "---" / "+++" inside a hunk are body lines
+        assert_eq!(
+            patches[0].hunks[0],
+            "@@ -1,3 +1,3 @@\n fn a() {\n--- removed\n+++ added\n }\n"
+        );
+        assert!(patches[0].hunks[1].ends_with("\\ No newline at end of file\n"));
+        assert_eq!(
+            patches[1].hunks,
+            vec!["@@ -1,1 +1,1 @@\n-old\n+new\n".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_parse_patch_errors() {
+        assert_eq!(parse_patch("just text\n"), Err(PatchError::NoHunks));
+        assert!(matches!(
+            parse_patch("@@ -1,2 +1,2 @@\n a\n"),
+            Err(PatchError::Malformed { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse_patch("@@ -1,1 +1,1 @@\n-a\n*b\n"),
+            Err(PatchError::Malformed { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_select_file_patch() {
+        let patches = parse_patch(DIFF).unwrap();
+        let selected = select_file_patch(patches.clone(), "/repo/README.md").unwrap();
+        assert_eq!(selected.old_path.as_deref(), Some("README.md"));
+
+        match select_file_patch(patches, "other.rs") {
+            Err(PatchError::FileNotInPatch { paths, .. }) => {
+                assert_eq!(paths, vec!["src/lib.rs", "README.md"])
+            }
+            other => panic!("Expected PatchError::FileNotInPatch, got {:?}", other),
+        }
+
+        // A single-file diff applies to whatever file is given
+        let single = parse_patch("@@ -1 +1 @@\n-a\n+b\n").unwrap();
+        let request = select_file_patch(single, "x.txt")
+            .unwrap()
+            .to_request("x.txt", "any", 0);
+        assert_eq!(request.edits[0].kind, EditKind::Hunk);
+        assert_eq!(request.edits[0].max_fuzz, Some(0));
+    }
+}
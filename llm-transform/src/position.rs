@@ -1,14 +1,16 @@
+use serde::{Deserialize, Serialize};
+
 /// Position in a text file (line and column numbers)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Position {
     /// Line number (1-indexed)
     pub line: usize,
-    /// Column number (1-indexed, in bytes)
+    /// Column number (1-indexed, in the units of the [`PositionEncoding`] used; bytes by default)
     pub column: usize,
 }
 
 /// Byte span in a text file
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Span {
     /// Starting byte offset
     pub byte_start: usize,
@@ -16,49 +18,563 @@ pub struct Span {
     pub byte_end: usize,
 }
 
+/// Unit that [`Position::column`] is counted in
+///
+/// LSP clients and most editors count UTF-16 code units; `utf32` counts
+/// Unicode scalar values (characters).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PositionEncoding {
+    /// UTF-8 bytes
+    #[default]
+    Utf8,
+    /// UTF-16 code units
+    Utf16,
+    /// Unicode scalar values
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Number of code units `c` occupies in this encoding
+    pub fn char_len(self, c: char) -> usize {
+        match self {
+            PositionEncoding::Utf8 => c.len_utf8(),
+            PositionEncoding::Utf16 => c.len_utf16(),
+            PositionEncoding::Utf32 => 1,
+        }
+    }
+
+    /// Number of code units `text` occupies in this encoding
+    pub fn len_of(self, text: &str) -> usize {
+        match self {
+            PositionEncoding::Utf8 => text.len(),
+            _ => text.chars().map(|c| self.char_len(c)).sum(),
+        }
+    }
+}
+
+/// Line break style of a text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    /// `\n` (Unix)
+    Lf,
+    /// `\r\n` (Windows)
+    CrLf,
+    /// Lone `\r` (classic Mac OS)
+    Cr,
+    /// More than one style in the same text
+    Mixed,
+}
+
+impl LineEnding {
+    /// Detect the line break style of `content`
+    ///
+    /// # Returns
+    /// * `Some(LineEnding)` - The style shared by every line break, or `Mixed`
+    /// * `None` - `content` has no line breaks
+    pub fn detect(content: &str) -> Option<Self> {
+        let mut detected = None;
+        for (end, len) in line_breaks(content) {
+            let ending = match (len, content.as_bytes()[end - 1]) {
+                (2, _) => LineEnding::CrLf,
+                (_, b'\r') => LineEnding::Cr,
+                _ => LineEnding::Lf,
+            };
+            match detected {
+                None => detected = Some(ending),
+                Some(previous) if previous != ending => return Some(LineEnding::Mixed),
+                Some(_) => {}
+            }
+        }
+        detected
+    }
+
+    /// The line break text, or `None` for `Mixed`
+    pub fn as_str(self) -> Option<&'static str> {
+        match self {
+            LineEnding::Lf => Some("\n"),
+            LineEnding::CrLf => Some("\r\n"),
+            LineEnding::Cr => Some("\r"),
+            LineEnding::Mixed => None,
+        }
+    }
+}
+
+/// `text` with every line break (`\n`, `\r\n` or lone `\r`) replaced by `line_break`
+pub(crate) fn with_line_break(text: &str, line_break: &str) -> String {
+    let normalized = text.replace("\r\n", "\n").replace('\r', "\n");
+    if line_break == "\n" {
+        normalized
+    } else {
+        normalized.replace('\n', line_break)
+    }
+}
+
+/// Line breaks in `text` as (offset just past the break, break length)
+///
+/// `\r\n` is a single two-byte break; a `\r` not followed by `\n` is a
+/// break of its own.
+fn line_breaks(text: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let bytes = text.as_bytes();
+    bytes.iter().enumerate().filter_map(move |(i, &b)| match b {
+        b'\n' if i > 0 && bytes[i - 1] == b'\r' => Some((i + 1, 2)),
+        b'\n' => Some((i + 1, 1)),
+        b'\r' if bytes.get(i + 1) == Some(&b'\n') => None,
+        b'\r' => Some((i + 1, 1)),
+        _ => None,
+    })
+}
+
+/// Line-start table for fast offset <-> position conversion
+///
+/// Lines end at `\n`, `\r\n` or a lone `\r`.
+///
+/// Built once per content in O(n); each conversion is then a binary search
+/// over the line starts. After an edit the table can be updated in place
+/// with [`LineIndex::apply_edit`] (or [`LineIndex::apply_edits`] for a
+/// batch) instead of being rebuilt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    /// Byte offset where each line starts (the first is always 0)
+    line_starts: Vec<usize>,
+    /// Byte length of the indexed content
+    len: usize,
+}
+
+impl LineIndex {
+    /// Index the line starts of `content`
+    pub fn new(content: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(line_breaks(content).map(|(end, _)| end));
+        Self {
+            line_starts,
+            len: content.len(),
+        }
+    }
+
+    /// Number of lines (a trailing newline starts an extra, empty line)
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Byte length of the indexed content
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the indexed content is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Byte span of a line (1-indexed), including its line break
+    pub fn line_span(&self, line: usize) -> Option<Span> {
+        let byte_start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let byte_end = self.line_starts.get(line).copied().unwrap_or(self.len);
+        Some(Span {
+            byte_start,
+            byte_end,
+        })
+    }
+
+    /// Number of lines holding text: like [`LineIndex::line_count`], but a
+    /// trailing line break does not start an extra line (an empty content has one line)
+    pub fn text_line_count(&self) -> usize {
+        let count = self.line_count();
+        if count > 1 && self.line_starts[count - 1] == self.len {
+            count - 1
+        } else {
+            count
+        }
+    }
+
+    /// Byte span of the lines `first..=last` (1-indexed), including the last line's break
+    ///
+    /// # Returns
+    /// * `Ok(Span)` - From the start of `first` to the end of `last`
+    /// * `Err(PositionError::LineOutOfRange)` - A line is 0 or past [`LineIndex::text_line_count`]
+    pub fn lines_span(&self, first: usize, last: usize) -> Result<Span, PositionError> {
+        let line_count = self.text_line_count();
+        for line in [first, last] {
+            if line == 0 || line > line_count {
+                return Err(PositionError::LineOutOfRange { line, line_count });
+            }
+        }
+        Ok(Span {
+            byte_start: self.line_starts[first - 1],
+            byte_end: self.line_starts.get(last).copied().unwrap_or(self.len),
+        })
+    }
+
+    /// Line (1-indexed) containing `byte_offset`; offsets past the end are on the last line
+    pub fn line_of(&self, byte_offset: usize) -> usize {
+        self.line_starts
+            .partition_point(|&start| start <= byte_offset)
+    }
+
+    /// Line break ending a line (1-indexed): `"\n"`, `"\r\n"`, `"\r"`, or `""` for the last line
+    pub fn line_break<'a>(&self, content: &'a str, line: usize) -> Option<&'a str> {
+        let span = self.line_span(line)?;
+        let text = self.line_text(content, line)?;
+        Some(&content[span.byte_start + text.len()..span.byte_end])
+    }
+
+    /// Text of a line (1-indexed), without its line break
+    fn line_text<'a>(&self, content: &'a str, line: usize) -> Option<&'a str> {
+        let span = self.line_span(line)?;
+        let text = &content[span.byte_start..span.byte_end];
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        Some(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    /// Convert a byte offset to a position with columns in `encoding` units
+    ///
+    /// An offset inside a multi-byte character maps to the column of that
+    /// character. Offsets past the end are reported on the last line.
+    ///
+    /// # Arguments
+    /// * `content` - The content this index was built from
+    /// * `byte_offset` - The byte offset to convert
+    /// * `encoding` - Unit the column is counted in
+    ///
+    /// # Returns
+    /// * `Position` with line and column (both 1-indexed)
+    pub fn byte_to_position(
+        &self,
+        content: &str,
+        byte_offset: usize,
+        encoding: PositionEncoding,
+    ) -> Position {
+        let line = self.line_of(byte_offset);
+        let line_start = self.line_starts[line - 1];
+
+        if encoding == PositionEncoding::Utf8 {
+            return Position {
+                line,
+                column: byte_offset - line_start + 1,
+            };
+        }
+
+        let mut end = byte_offset.min(content.len());
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        Position {
+            line,
+            column: encoding.len_of(&content[line_start..end]) + 1,
+        }
+    }
+
+    /// Convert a position with columns in `encoding` units to a byte offset
+    ///
+    /// # Arguments
+    /// * `content` - The content this index was built from
+    /// * `position` - The position to convert
+    /// * `encoding` - Unit the column is counted in
+    ///
+    /// # Returns
+    /// * `Ok(usize)` - The byte offset
+    /// * `Err(PositionError)` - Line or column out of range (never clamped), or
+    ///   a UTF-16 column inside a surrogate pair
+    pub fn position_to_byte(
+        &self,
+        content: &str,
+        position: Position,
+        encoding: PositionEncoding,
+    ) -> Result<usize, PositionError> {
+        let line_start = match self.line_span(position.line) {
+            Some(span) => span.byte_start,
+            None => {
+                return Err(PositionError::LineOutOfRange {
+                    line: position.line,
+                    line_count: self.line_count(),
+                });
+            }
+        };
+        let line = self.line_text(content, position.line).unwrap_or("");
+
+        let max_column = encoding.len_of(line) + 1;
+        if position.column == 0 || position.column > max_column {
+            return Err(PositionError::ColumnOutOfRange {
+                line: position.line,
+                column: position.column,
+                max_column,
+            });
+        }
+
+        if encoding == PositionEncoding::Utf8 {
+            return Ok(line_start + position.column - 1);
+        }
+
+        let target = position.column - 1;
+        let mut units = 0;
+        for (offset, c) in line.char_indices() {
+            if units >= target {
+                break;
+            }
+            units += encoding.char_len(c);
+            if units > target {
+                return Err(PositionError::ColumnInsideCharacter {
+                    line: position.line,
+                    column: position.column,
+                });
+            }
+            if units == target {
+                return Ok(line_start + offset + c.len_utf8());
+            }
+        }
+        Ok(line_start)
+    }
+
+    /// Update the index after `span` was replaced by `replacement`
+    ///
+    /// Only the line starts around the span are recomputed; the ones after it
+    /// are shifted. A `\r` just before the span or a `\n` just after it is
+    /// rescanned too, since the edit can join or split a `\r\n` pair. The
+    /// result is identical to indexing the edited content.
+    ///
+    /// # Arguments
+    /// * `content` - The content before the edit
+    /// * `span` - The replaced byte span
+    /// * `replacement` - The text that replaced it
+    pub fn apply_edit(&mut self, content: &str, span: Span, replacement: &str) {
+        let bytes = content.as_bytes();
+        let cr_before = span.byte_start > 0 && bytes[span.byte_start - 1] == b'\r';
+        let lf_after = bytes.get(span.byte_end) == Some(&b'\n');
+        self.replace(span, replacement, cr_before, lf_after);
+    }
+
+    /// Update the index after a batch of non-overlapping edits
+    ///
+    /// The edits are given in original offsets and in application order
+    /// (`byte_start` descending), as [`crate::apply_edits_with_options`]
+    /// applies them. Each one is folded in like [`LineIndex::apply_edit`],
+    /// without building the intermediate contents.
+    ///
+    /// # Arguments
+    /// * `content` - The content before the edits
+    /// * `edits` - Each replaced byte span with the text that replaced it
+    pub fn apply_edits<'a>(
+        &mut self,
+        content: &str,
+        edits: impl IntoIterator<Item = (Span, &'a str)>,
+    ) {
+        let bytes = content.as_bytes();
+        // Start of the previous (higher) edit and the byte that now follows
+        // that offset, which is not the original one if the edit replaced it
+        let mut previous: Option<(usize, Option<u8>)> = None;
+        for (span, replacement) in edits {
+            // Everything below the span is still original content
+            let cr_before = span.byte_start > 0 && bytes[span.byte_start - 1] == b'\r';
+            let after = match previous {
+                Some((start, after)) if start == span.byte_end => after,
+                _ => bytes.get(span.byte_end).copied(),
+            };
+            self.replace(span, replacement, cr_before, after == Some(b'\n'));
+            previous = Some((span.byte_start, replacement.bytes().next().or(after)));
+        }
+    }
+
+    /// Splice the line starts of `replacement` over `span`, rescanning the
+    /// `\r` before and the `\n` after it if present
+    fn replace(&mut self, span: Span, replacement: &str, cr_before: bool, lf_after: bool) {
+        let window_start = span.byte_start - usize::from(cr_before);
+        let window_end = span.byte_end + usize::from(lf_after);
+        let mut window = String::with_capacity(replacement.len() + 2);
+        if cr_before {
+            window.push('\r');
+        }
+        window.push_str(replacement);
+        if lf_after {
+            window.push('\n');
+        }
+
+        let first = self
+            .line_starts
+            .partition_point(|&start| start <= window_start);
+        let last = self
+            .line_starts
+            .partition_point(|&start| start <= window_end);
+        let inserted: Vec<usize> = line_breaks(&window)
+            .map(|(end, _)| window_start + end)
+            .collect();
+        let shifted_from = first + inserted.len();
+        self.line_starts.splice(first..last, inserted);
+
+        let removed = span.byte_end - span.byte_start;
+        for start in &mut self.line_starts[shifted_from..] {
+            *start = *start - removed + replacement.len();
+        }
+        self.len = self.len - removed + replacement.len();
+    }
+}
+
 /// Convert a byte offset to line and column position
 ///
 /// # Arguments
 /// * `content` - The file content as a string
+/// * `line_index` - Line index of `content`
 /// * `byte_offset` - The byte offset to convert
 ///
 /// # Returns
 /// * `Position` with line and column (both 1-indexed)
-/// * Returns line=content.lines().count()+1 if offset is past end
-pub fn byte_to_position(content: &str, byte_offset: usize) -> Position {
-    let mut line = 1;
-    let mut current_offset = 0;
-    let mut line_start_offset = 0;
+/// * Offsets past the end are reported on the last line
+pub fn byte_to_position(content: &str, line_index: &LineIndex, byte_offset: usize) -> Position {
+    byte_to_position_with_encoding(content, line_index, byte_offset, PositionEncoding::Utf8)
+}
 
-    for line_str in content.lines() {
-        let line_bytes = line_str.len() + 1; // +1 for newline
+/// Convert a byte offset to a position with columns in `encoding` units
+///
+/// An offset inside a multi-byte character maps to the column of that
+/// character.
+///
+/// # Arguments
+/// * `content` - The file content as a string
+/// * `line_index` - Line index of `content`
+/// * `byte_offset` - The byte offset to convert
+/// * `encoding` - Unit the column is counted in
+///
+/// # Returns
+/// * `Position` with line and column (both 1-indexed)
+pub fn byte_to_position_with_encoding(
+    content: &str,
+    line_index: &LineIndex,
+    byte_offset: usize,
+    encoding: PositionEncoding,
+) -> Position {
+    line_index.byte_to_position(content, byte_offset, encoding)
+}
 
-        if current_offset + line_bytes > byte_offset {
-            // Target is in this line
-            let column = byte_offset - line_start_offset + 1;
-            return Position { line, column };
-        }
+/// Error converting a line/column position to a byte offset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PositionError {
+    /// Line is 0 or past the last line
+    LineOutOfRange { line: usize, line_count: usize },
+    /// Column is 0 or past the end of the line
+    ColumnOutOfRange {
+        line: usize,
+        column: usize,
+        /// Largest valid column on this line (one past the last character)
+        max_column: usize,
+    },
+    /// Column points between the two UTF-16 code units of a surrogate pair
+    ColumnInsideCharacter { line: usize, column: usize },
+}
 
-        current_offset += line_bytes;
-        line_start_offset = current_offset;
-        line += 1;
+impl std::fmt::Display for PositionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PositionError::LineOutOfRange { line, line_count } => {
+                write!(
+                    f,
+                    "Line {} out of range (file has {} line(s))",
+                    line, line_count
+                )
+            }
+            PositionError::ColumnOutOfRange {
+                line,
+                column,
+                max_column,
+            } => {
+                write!(
+                    f,
+                    "Column {} out of range on line {} (max column: {})",
+                    column, line, max_column
+                )
+            }
+            PositionError::ColumnInsideCharacter { line, column } => {
+                write!(
+                    f,
+                    "Column {} on line {} is inside a character",
+                    column, line
+                )
+            }
+        }
     }
+}
+
+impl std::error::Error for PositionError {}
+
+/// Convert a line and column position to a byte offset
+///
+/// This is the inverse of [`byte_to_position`]. Lines and columns are
+/// 1-indexed, columns count bytes. The largest valid column on a line is one
+/// past its last character (the position of the line break). A file ending
+/// in a newline has an extra empty last line.
+///
+/// # Arguments
+/// * `content` - The file content as a string
+/// * `line_index` - Line index of `content`
+/// * `position` - The position to convert
+///
+/// # Returns
+/// * `Ok(usize)` - The byte offset
+/// * `Err(PositionError)` - Line or column out of range (never clamped)
+pub fn position_to_byte(
+    content: &str,
+    line_index: &LineIndex,
+    position: Position,
+) -> Result<usize, PositionError> {
+    position_to_byte_with_encoding(content, line_index, position, PositionEncoding::Utf8)
+}
 
-    // Offset is past the end (or at the very end)
-    Position { line, column: byte_offset - line_start_offset + 1 }
+/// Convert a line and column position with columns in `encoding` units to a byte offset
+///
+/// # Arguments
+/// * `content` - The file content as a string
+/// * `line_index` - Line index of `content`
+/// * `position` - The position to convert
+/// * `encoding` - Unit the column is counted in
+///
+/// # Returns
+/// * `Ok(usize)` - The byte offset
+/// * `Err(PositionError)` - Line or column out of range (never clamped), or
+///   a UTF-16 column inside a surrogate pair
+pub fn position_to_byte_with_encoding(
+    content: &str,
+    line_index: &LineIndex,
+    position: Position,
+    encoding: PositionEncoding,
+) -> Result<usize, PositionError> {
+    line_index.position_to_byte(content, position, encoding)
 }
 
 /// Convert a byte span to start and end positions
 ///
 /// # Arguments
 /// * `content` - The file content as a string
+/// * `line_index` - Line index of `content`
 /// * `span` - The byte span to convert
 ///
 /// # Returns
 /// * `(Position, Position)` - Start and end positions
-pub fn span_to_positions(content: &str, span: Span) -> (Position, Position) {
-    let start = byte_to_position(content, span.byte_start);
-    let end = byte_to_position(content, span.byte_end);
+pub fn span_to_positions(
+    content: &str,
+    line_index: &LineIndex,
+    span: Span,
+) -> (Position, Position) {
+    span_to_positions_with_encoding(content, line_index, span, PositionEncoding::Utf8)
+}
+
+/// Convert a byte span to start and end positions with columns in `encoding` units
+///
+/// # Arguments
+/// * `content` - The file content as a string
+/// * `line_index` - Line index of `content`
+/// * `span` - The byte span to convert
+/// * `encoding` - Unit the columns are counted in
+///
+/// # Returns
+/// * `(Position, Position)` - Start and end positions
+pub fn span_to_positions_with_encoding(
+    content: &str,
+    line_index: &LineIndex,
+    span: Span,
+    encoding: PositionEncoding,
+) -> (Position, Position) {
+    let start = line_index.byte_to_position(content, span.byte_start, encoding);
+    let end = line_index.byte_to_position(content, span.byte_end, encoding);
     (start, end)
 }
 
@@ -69,7 +585,8 @@ mod tests {
     #[test]
     fn test_byte_to_position_start() {
         let content = "Hello\nWorld";
-        let pos = byte_to_position(content, 0);
+        let index = LineIndex::new(content);
+        let pos = byte_to_position(content, &index, 0);
 
         assert_eq!(pos.line, 1);
         assert_eq!(pos.column, 1);
@@ -78,8 +595,9 @@ mod tests {
     #[test]
     fn test_byte_to_position_after_newline() {
         let content = "Hello\nWorld";
+        let index = LineIndex::new(content);
         // Byte offset 6 is right after '\n' (H=0, e=1, l=2, l=3, o=4, \n=5, W=6)
-        let pos = byte_to_position(content, 6);
+        let pos = byte_to_position(content, &index, 6);
 
         assert_eq!(pos.line, 2);
         assert_eq!(pos.column, 1);
@@ -88,8 +606,9 @@ mod tests {
     #[test]
     fn test_byte_to_position_middle() {
         let content = "Hello\nWorld";
+        let index = LineIndex::new(content);
         // Byte offset 3 is the second 'l' in "Hello"
-        let pos = byte_to_position(content, 3);
+        let pos = byte_to_position(content, &index, 3);
 
         assert_eq!(pos.line, 1);
         assert_eq!(pos.column, 4); // 1-indexed, so column 4
@@ -98,12 +617,13 @@ mod tests {
     #[test]
     fn test_span_to_positions() {
         let content = "Hello\nWorld";
+        let index = LineIndex::new(content);
         let span = Span {
             byte_start: 0,
             byte_end: 5,
         };
 
-        let (start, end) = span_to_positions(content, span);
+        let (start, end) = span_to_positions(content, &index, span);
 
         // Start at line 1, column 1
         assert_eq!(start.line, 1);
@@ -113,4 +633,391 @@ mod tests {
         assert_eq!(end.line, 1);
         assert_eq!(end.column, 6);
     }
+
+    #[test]
+    fn test_position_to_byte() {
+        let content = "Hello\nWorld\n";
+        let index = LineIndex::new(content);
+
+        assert_eq!(
+            position_to_byte(content, &index, Position { line: 1, column: 1 }),
+            Ok(0)
+        );
+        assert_eq!(
+            position_to_byte(content, &index, Position { line: 1, column: 6 }),
+            Ok(5)
+        );
+        assert_eq!(
+            position_to_byte(content, &index, Position { line: 2, column: 3 }),
+            Ok(8)
+        );
+        // Empty last line after the trailing newline
+        assert_eq!(
+            position_to_byte(content, &index, Position { line: 3, column: 1 }),
+            Ok(12)
+        );
+
+        // Round trip with byte_to_position
+        for offset in 0..=content.len() {
+            let pos = byte_to_position(content, &index, offset);
+            assert_eq!(position_to_byte(content, &index, pos), Ok(offset));
+        }
+    }
+
+    #[test]
+    fn test_position_to_byte_out_of_range() {
+        let content = "Hello\nWorld";
+        let index = LineIndex::new(content);
+
+        assert_eq!(
+            position_to_byte(content, &index, Position { line: 3, column: 1 }),
+            Err(PositionError::LineOutOfRange {
+                line: 3,
+                line_count: 2
+            })
+        );
+        assert_eq!(
+            position_to_byte(content, &index, Position { line: 0, column: 1 }),
+            Err(PositionError::LineOutOfRange {
+                line: 0,
+                line_count: 2
+            })
+        );
+        assert_eq!(
+            position_to_byte(content, &index, Position { line: 1, column: 7 }),
+            Err(PositionError::ColumnOutOfRange {
+                line: 1,
+                column: 7,
+                max_column: 6
+            })
+        );
+        assert_eq!(
+            position_to_byte(content, &index, Position { line: 2, column: 0 }),
+            Err(PositionError::ColumnOutOfRange {
+                line: 2,
+                column: 0,
+                max_column: 6
+            })
+        );
+    }
+
+    #[test]
+    fn test_position_encodings() {
+        // 'é' is 2 bytes / 1 UTF-16 unit, '😀' is 4 bytes / 2 UTF-16 units
+        let content = "x\ncafé 😀 ok\n";
+        let index = LineIndex::new(content);
+        let ok = content.find("ok").unwrap();
+
+        let utf8 = byte_to_position_with_encoding(content, &index, ok, PositionEncoding::Utf8);
+        let utf16 = byte_to_position_with_encoding(content, &index, ok, PositionEncoding::Utf16);
+        let utf32 = byte_to_position_with_encoding(content, &index, ok, PositionEncoding::Utf32);
+        assert_eq!(
+            utf8,
+            Position {
+                line: 2,
+                column: 12
+            }
+        );
+        assert_eq!(utf16, Position { line: 2, column: 9 });
+        assert_eq!(utf32, Position { line: 2, column: 8 });
+
+        for (pos, encoding) in [
+            (utf8, PositionEncoding::Utf8),
+            (utf16, PositionEncoding::Utf16),
+            (utf32, PositionEncoding::Utf32),
+        ] {
+            assert_eq!(
+                position_to_byte_with_encoding(content, &index, pos, encoding),
+                Ok(ok)
+            );
+        }
+
+        // Round trip over every character boundary
+        for encoding in [PositionEncoding::Utf16, PositionEncoding::Utf32] {
+            for offset in (0..=content.len()).filter(|&i| content.is_char_boundary(i)) {
+                let pos = byte_to_position_with_encoding(content, &index, offset, encoding);
+                assert_eq!(
+                    position_to_byte_with_encoding(content, &index, pos, encoding),
+                    Ok(offset)
+                );
+            }
+        }
+
+        let span = Span {
+            byte_start: ok,
+            byte_end: ok + 2,
+        };
+        let (start, end) =
+            span_to_positions_with_encoding(content, &index, span, PositionEncoding::Utf16);
+        assert_eq!((start.column, end.column), (9, 11));
+    }
+
+    #[test]
+    fn test_position_encoding_errors() {
+        let content = "a😀b";
+        let index = LineIndex::new(content);
+
+        // Between the two halves of the surrogate pair
+        assert_eq!(
+            position_to_byte_with_encoding(
+                content,
+                &index,
+                Position { line: 1, column: 3 },
+                PositionEncoding::Utf16
+            ),
+            Err(PositionError::ColumnInsideCharacter { line: 1, column: 3 })
+        );
+        assert_eq!(
+            position_to_byte_with_encoding(
+                content,
+                &index,
+                Position { line: 1, column: 6 },
+                PositionEncoding::Utf16
+            ),
+            Err(PositionError::ColumnOutOfRange {
+                line: 1,
+                column: 6,
+                max_column: 5
+            })
+        );
+        assert_eq!(
+            position_to_byte_with_encoding(
+                content,
+                &index,
+                Position { line: 1, column: 4 },
+                PositionEncoding::Utf32
+            ),
+            Ok(content.len())
+        );
+    }
+
+    #[test]
+    fn test_line_index_lookups() {
+        let content = "ab\n\ncd\nef";
+        let index = LineIndex::new(content);
+
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(
+            index.line_span(3),
+            Some(Span {
+                byte_start: 4,
+                byte_end: 7
+            })
+        );
+        assert_eq!(index.line_span(0), None);
+        assert_eq!(index.line_span(5), None);
+
+        for offset in 0..=content.len() {
+            let pos = index.byte_to_position(content, offset, PositionEncoding::Utf8);
+            assert_eq!(
+                index.position_to_byte(content, pos, PositionEncoding::Utf8),
+                Ok(offset)
+            );
+        }
+        assert_eq!(
+            index.byte_to_position(content, 4, PositionEncoding::Utf8),
+            Position { line: 3, column: 1 }
+        );
+    }
+
+    #[test]
+    fn test_lines_span() {
+        let index = LineIndex::new("one\ntwo\nthree\n");
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.text_line_count(), 3);
+        assert_eq!(
+            index.lines_span(2, 3),
+            Ok(Span {
+                byte_start: 4,
+                byte_end: 14
+            })
+        );
+        assert_eq!(
+            index.lines_span(2, 4),
+            Err(PositionError::LineOutOfRange {
+                line: 4,
+                line_count: 3
+            })
+        );
+        assert_eq!(
+            index.lines_span(0, 1),
+            Err(PositionError::LineOutOfRange {
+                line: 0,
+                line_count: 3
+            })
+        );
+
+        // Last line without a line break, and an empty content
+        let index = LineIndex::new("one\r\ntwo");
+        assert_eq!(index.text_line_count(), 2);
+        assert_eq!(
+            index.lines_span(2, 2),
+            Ok(Span {
+                byte_start: 5,
+                byte_end: 8
+            })
+        );
+        assert_eq!(LineIndex::new("").text_line_count(), 1);
+        assert_eq!(
+            LineIndex::new("").lines_span(1, 1),
+            Ok(Span {
+                byte_start: 0,
+                byte_end: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_line_index_apply_edit() {
+        let cases = [
+            ("one\ntwo\nthree\n", 4, 7, "2"),
+            ("one\ntwo\nthree\n", 2, 9, "X\nY"),
+            ("one\ntwo\nthree\n", 3, 4, ""),
+            ("one\ntwo\nthree\n", 0, 0, "zero\n"),
+            ("one\ntwo\nthree\n", 14, 14, "four\nfive"),
+            ("", 0, 0, "\n\n"),
+            ("a\nb\nc\nd", 1, 6, "\n"),
+            // Joining and splitting \r\n pairs at the span edges
+            ("a\r\nb", 2, 2, "x"),
+            ("a\rb", 2, 2, "\n"),
+            ("a\nb", 1, 1, "\r"),
+            ("a\r\r\nb", 2, 3, ""),
+            ("a\r\nb\rc", 1, 2, "\r\n\r"),
+        ];
+
+        for (content, byte_start, byte_end, replacement) in cases {
+            let mut index = LineIndex::new(content);
+            index.apply_edit(
+                content,
+                Span {
+                    byte_start,
+                    byte_end,
+                },
+                replacement,
+            );
+
+            let edited = format!(
+                "{}{}{}",
+                &content[..byte_start],
+                replacement,
+                &content[byte_end..]
+            );
+            assert_eq!(
+                index,
+                LineIndex::new(&edited),
+                "edit {}..{} -> {:?} on {:?}",
+                byte_start,
+                byte_end,
+                replacement,
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn test_line_index_apply_edits() {
+        // Batches in application order, including adjacent edits that join
+        // or split a \r\n pair across their boundary
+        let cases = [
+            (
+                "one\ntwo\nthree\n",
+                vec![(8, 13, "3\n3"), (4, 7, "2"), (0, 0, "zero\n")],
+            ),
+            ("a\rb", vec![(2, 3, "\nc"), (1, 2, "\r")]),
+            ("a\r\nb", vec![(2, 3, ""), (1, 2, "\r")]),
+            ("ab\ncd", vec![(2, 2, "\n"), (1, 2, "\r"), (1, 1, "")]),
+            ("x\ny", vec![(3, 3, "\r"), (1, 3, "")]),
+        ];
+
+        for (content, edits) in cases {
+            let mut index = LineIndex::new(content);
+            index.apply_edits(
+                content,
+                edits.iter().map(|&(byte_start, byte_end, replacement)| {
+                    (
+                        Span {
+                            byte_start,
+                            byte_end,
+                        },
+                        replacement,
+                    )
+                }),
+            );
+
+            let mut edited = content.to_string();
+            for &(byte_start, byte_end, replacement) in &edits {
+                edited.replace_range(byte_start..byte_end, replacement);
+            }
+            assert_eq!(
+                index,
+                LineIndex::new(&edited),
+                "edits {:?} on {:?}",
+                edits,
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn test_crlf_and_cr_positions() {
+        let content = "one\r\ntwo\rthree\nfour";
+        let index = LineIndex::new(content);
+
+        assert_eq!(index.line_count(), 4);
+        let three = content.find("three").unwrap();
+        let four = content.find("four").unwrap();
+        assert_eq!(
+            byte_to_position(content, &index, three),
+            Position { line: 3, column: 1 }
+        );
+        assert_eq!(
+            byte_to_position(content, &index, four),
+            Position { line: 4, column: 1 }
+        );
+        assert_eq!(
+            position_to_byte(content, &index, Position { line: 2, column: 1 }),
+            Ok(5)
+        );
+        assert_eq!(
+            position_to_byte(content, &index, Position { line: 4, column: 2 }),
+            Ok(four + 1)
+        );
+
+        // The line break is not part of the line's columns
+        assert_eq!(
+            position_to_byte(content, &index, Position { line: 1, column: 4 }),
+            Ok(3)
+        );
+        assert_eq!(
+            position_to_byte(content, &index, Position { line: 1, column: 5 }),
+            Err(PositionError::ColumnOutOfRange {
+                line: 1,
+                column: 5,
+                max_column: 4
+            })
+        );
+        assert_eq!(
+            index.line_span(1),
+            Some(Span {
+                byte_start: 0,
+                byte_end: 5
+            })
+        );
+        assert_eq!(index.line_break(content, 1), Some("\r\n"));
+        assert_eq!(index.line_break(content, 2), Some("\r"));
+        assert_eq!(index.line_break(content, 4), Some(""));
+        assert_eq!(index.line_of(three + 2), 3);
+    }
+
+    #[test]
+    fn test_line_ending_detect() {
+        assert_eq!(LineEnding::detect("a\nb\n"), Some(LineEnding::Lf));
+        assert_eq!(LineEnding::detect("a\r\nb\r\n"), Some(LineEnding::CrLf));
+        assert_eq!(LineEnding::detect("a\rb\r"), Some(LineEnding::Cr));
+        assert_eq!(LineEnding::detect("a\r\nb\n"), Some(LineEnding::Mixed));
+        assert_eq!(LineEnding::detect("a\rb\r\n"), Some(LineEnding::Mixed));
+        assert_eq!(LineEnding::detect("no breaks"), None);
+        assert_eq!(LineEnding::CrLf.as_str(), Some("\r\n"));
+        assert_eq!(LineEnding::Mixed.as_str(), None);
+    }
 }
@@ -0,0 +1,260 @@
+use regex::Regex;
+
+use crate::edit::{Edit, EditError};
+use crate::position::Span;
+
+/// Which match of a search text an edit refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Occurrence {
+    /// The text must match exactly once
+    Unique,
+    /// The n-th match (1-indexed) in file order
+    Nth(usize),
+}
+
+/// Find every non-overlapping occurrence of `needle` in `content`
+///
+/// # Arguments
+/// * `content` - The text to search
+/// * `needle` - Literal text to find (must not be empty)
+///
+/// # Returns
+/// * Spans of the matches, in file order
+pub fn find_occurrences(content: &str, needle: &str) -> Vec<Span> {
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    content
+        .match_indices(needle)
+        .map(|(byte_start, text)| Span {
+            byte_start,
+            byte_end: byte_start + text.len(),
+        })
+        .collect()
+}
+
+/// Select one occurrence of `needle` in `content`
+///
+/// # Arguments
+/// * `content` - The text to search
+/// * `needle` - Literal text to find
+/// * `occurrence` - Which match to select
+///
+/// # Returns
+/// * `Ok(Span)` - Span of the selected match
+/// * `Err(EditError::SearchMismatch)` - Not found, not unique, or fewer matches than
+///   the requested occurrence; lists every match
+/// * `Err(EditError::InvalidEditSpec)` - Empty needle or occurrence 0
+pub fn select_occurrence(
+    content: &str,
+    needle: &str,
+    occurrence: Occurrence,
+) -> Result<Span, EditError> {
+    if needle.is_empty() {
+        return Err(EditError::InvalidEditSpec("find text is empty".to_string()));
+    }
+    if occurrence == Occurrence::Nth(0) {
+        return Err(EditError::InvalidEditSpec(
+            "occurrence is 1-indexed".to_string(),
+        ));
+    }
+
+    let matches = find_occurrences(content, needle);
+    let selected = match occurrence {
+        Occurrence::Unique if matches.len() == 1 => Some(matches[0]),
+        Occurrence::Unique => None,
+        Occurrence::Nth(n) => matches.get(n - 1).copied(),
+    };
+
+    selected.ok_or_else(|| EditError::SearchMismatch {
+        find: needle.to_string(),
+        occurrence: match occurrence {
+            Occurrence::Unique => None,
+            Occurrence::Nth(n) => Some(n),
+        },
+        matches,
+    })
+}
+
+/// Expand a regex search-and-replace into one edit per match
+///
+/// `replacement` may refer to capture groups as `$1`, `${1}`, `$name` or
+/// `${name}` (`$$` for a literal `$`). Each edit carries the matched text as
+/// its `expected_text`.
+///
+/// # Arguments
+/// * `content` - The text to search
+/// * `pattern` - Regular expression (`regex` crate syntax)
+/// * `replacement` - Replacement template
+/// * `max_replacements` - Replace at most this many matches, in file order
+/// * `expected_count` - Fail unless the pattern matches exactly this many times
+///
+/// # Returns
+/// * `Ok(Vec<Edit>)` - One edit per replaced match, in file order (without `expected_checksum`)
+/// * `Err(EditError::InvalidEditSpec)` - The pattern does not compile
+/// * `Err(EditError::MatchCountMismatch)` - The match count differs from `expected_count`
+pub fn regex_edits(
+    content: &str,
+    pattern: &str,
+    replacement: &str,
+    max_replacements: Option<usize>,
+    expected_count: Option<usize>,
+) -> Result<Vec<Edit>, EditError> {
+    let regex = Regex::new(pattern)
+        .map_err(|e| EditError::InvalidEditSpec(format!("invalid pattern: {}", e)))?;
+    let captures: Vec<regex::Captures> = regex.captures_iter(content).collect();
+
+    if let Some(expected) = expected_count
+        && captures.len() != expected
+    {
+        return Err(EditError::MatchCountMismatch {
+            pattern: pattern.to_string(),
+            expected,
+            matches: captures
+                .iter()
+                .filter_map(|caps| caps.get(0))
+                .map(|m| Span {
+                    byte_start: m.start(),
+                    byte_end: m.end(),
+                })
+                .collect(),
+        });
+    }
+
+    Ok(captures
+        .iter()
+        .take(max_replacements.unwrap_or(usize::MAX))
+        .filter_map(|caps| {
+            let m = caps.get(0)?;
+            let mut expanded = String::new();
+            caps.expand(replacement, &mut expanded);
+            Some(Edit {
+                byte_start: m.start(),
+                byte_end: m.end(),
+                replacement: expanded,
+                expected_text: Some(m.as_str().to_string()),
+                ..Default::default()
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_occurrences() {
+        let content = "foo bar foo baz foo";
+
+        let matches = find_occurrences(content, "foo");
+        assert_eq!(matches.len(), 3);
+        assert_eq!(
+            matches[1],
+            Span {
+                byte_start: 8,
+                byte_end: 11
+            }
+        );
+
+        // Non-overlapping, left to right
+        assert_eq!(find_occurrences("aaaa", "aa").len(), 2);
+        assert!(find_occurrences(content, "").is_empty());
+    }
+
+    #[test]
+    fn test_select_occurrence() {
+        let content = "foo bar foo baz foo";
+
+        assert_eq!(
+            select_occurrence(content, "foo", Occurrence::Nth(2)).unwrap(),
+            Span {
+                byte_start: 8,
+                byte_end: 11
+            }
+        );
+        assert_eq!(
+            select_occurrence(content, "bar", Occurrence::Unique).unwrap(),
+            Span {
+                byte_start: 4,
+                byte_end: 7
+            }
+        );
+
+        match select_occurrence(content, "foo", Occurrence::Unique) {
+            Err(EditError::SearchMismatch {
+                occurrence: None,
+                matches,
+                ..
+            }) => {
+                let starts: Vec<usize> = matches.iter().map(|m| m.byte_start).collect();
+                assert_eq!(starts, vec![0, 8, 16]);
+            }
+            other => panic!("Expected EditError::SearchMismatch, got {:?}", other),
+        }
+        match select_occurrence(content, "foo", Occurrence::Nth(4)) {
+            Err(EditError::SearchMismatch {
+                occurrence: Some(4),
+                matches,
+                ..
+            }) => assert_eq!(matches.len(), 3),
+            other => panic!("Expected EditError::SearchMismatch, got {:?}", other),
+        }
+        match select_occurrence(content, "qux", Occurrence::Unique) {
+            Err(EditError::SearchMismatch { matches, .. }) => assert!(matches.is_empty()),
+            other => panic!("Expected EditError::SearchMismatch, got {:?}", other),
+        }
+        assert!(matches!(
+            select_occurrence(content, "foo", Occurrence::Nth(0)),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+    }
+
+    #[test]
+    fn test_regex_edits() {
+        let content = "let a = foo(1);\nlet b = foo(22);\nlet c = bar(3);";
+
+        let edits = regex_edits(content, r"foo\((\d+)\)", "bar($1)", None, None).unwrap();
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[0].replacement, "bar(1)");
+        assert_eq!(edits[1].replacement, "bar(22)");
+        assert_eq!(edits[1].expected_text.as_deref(), Some("foo(22)"));
+        assert_eq!(&content[edits[1].byte_start..edits[1].byte_end], "foo(22)");
+
+        let edits =
+            regex_edits(content, r"let (?P<name>\w)", "const ${name}", Some(2), None).unwrap();
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[0].replacement, "const a");
+
+        assert!(
+            regex_edits(content, r"nothing", "x", None, None)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_regex_edits_errors() {
+        let content = "foo(1) foo(2) foo(3)";
+
+        match regex_edits(content, r"foo\(\d\)", "x", None, Some(2)) {
+            Err(EditError::MatchCountMismatch {
+                expected: 2,
+                matches,
+                ..
+            }) => assert_eq!(matches.len(), 3),
+            other => panic!("Expected EditError::MatchCountMismatch, got {:?}", other),
+        }
+        // The guard counts every match, not just the replaced ones
+        assert_eq!(
+            regex_edits(content, r"foo", "x", Some(1), Some(3))
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(matches!(
+            regex_edits(content, r"foo(", "x", None, None),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+    }
+}
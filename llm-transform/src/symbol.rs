@@ -0,0 +1,644 @@
+use serde::{Deserialize, Serialize};
+use tree_sitter::Node;
+
+use crate::edit::EditError;
+use crate::language::Language;
+use crate::position::Span;
+use crate::syntax;
+
+/// Part of a symbol an edit replaces
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolTarget {
+    /// The whole item, from its first keyword or modifier to its end
+    #[default]
+    Whole,
+    /// The item's body (block, class body, ...), including its delimiters
+    Body,
+    /// Everything before the body, without trailing whitespace
+    Signature,
+    /// The item's name
+    Name,
+}
+
+impl SymbolTarget {
+    /// Name of the target as used in JSON
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymbolTarget::Whole => "whole",
+            SymbolTarget::Body => "body",
+            SymbolTarget::Signature => "signature",
+            SymbolTarget::Name => "name",
+        }
+    }
+}
+
+/// A named item (function, type, module, ...) found in source code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol {
+    /// Item kind, as the language spells it ("fn", "impl", "class", "def", ...)
+    pub kind: String,
+    /// Item name (for a Rust impl: the type, or "Trait for Type")
+    pub name: String,
+    /// Qualified path that addresses exactly this item, e.g. "impl Foo::fn bar"
+    pub path: String,
+    /// Span of the whole item
+    pub span: Span,
+    /// Span of the name
+    pub name_span: Span,
+    /// Span of the body, if the item has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Span>,
+    /// First line of the item (1-indexed)
+    pub line_start: usize,
+    /// Last line of the item (1-indexed)
+    pub line_end: usize,
+    /// Number of enclosing items (0 at the top level)
+    pub depth: usize,
+    /// End of the signature: the last token before the body
+    #[serde(skip)]
+    signature_end: usize,
+    /// Kind and name of the item and every enclosing item, outermost first
+    #[serde(skip)]
+    segments: Vec<Segment>,
+}
+
+impl Symbol {
+    /// Span of `target` within this symbol
+    ///
+    /// # Returns
+    /// * `Ok(Span)` - The span to replace
+    /// * `Err(EditError::InvalidEditSpec)` - The target is "body" and the item has none
+    pub fn target_span(&self, target: SymbolTarget) -> Result<Span, EditError> {
+        match target {
+            SymbolTarget::Whole => Ok(self.span),
+            SymbolTarget::Name => Ok(self.name_span),
+            SymbolTarget::Body => self.body.ok_or_else(|| {
+                EditError::InvalidEditSpec(format!("symbol \"{}\" has no body", self.path))
+            }),
+            SymbolTarget::Signature => Ok(Span {
+                byte_start: self.span.byte_start,
+                byte_end: self.signature_end,
+            }),
+        }
+    }
+
+    /// Whether the symbol path `query` names this symbol
+    ///
+    /// The last segment of the query must name the symbol itself, and any
+    /// earlier segments its directly enclosing items. Returns the number of
+    /// enclosing items the query left out (0 for a fully qualified match).
+    fn matches(&self, query: &[Segment]) -> Option<usize> {
+        if query.is_empty() || query.len() > self.segments.len() {
+            return None;
+        }
+        let skipped = self.segments.len() - query.len();
+        self.segments[skipped..]
+            .iter()
+            .zip(query)
+            .all(|(segment, wanted)| segment.matches(wanted))
+            .then_some(skipped)
+    }
+}
+
+/// One segment of a symbol path: an optional kind keyword and a name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Segment {
+    kind: Option<String>,
+    name: String,
+}
+
+impl Segment {
+    fn parse(text: &str) -> Self {
+        let text = text.trim();
+        match text.split_once(char::is_whitespace) {
+            Some((keyword, name)) if KEYWORDS.contains(&keyword) => Segment {
+                kind: Some(keyword.to_string()),
+                name: name.trim().to_string(),
+            },
+            _ => Segment {
+                kind: None,
+                name: text.to_string(),
+            },
+        }
+    }
+
+    /// Whether this (found) segment satisfies the `wanted` query segment
+    fn matches(&self, wanted: &Segment) -> bool {
+        // "Foo" also names the trait impls "Trait for Foo"
+        let name_matches = self.name == wanted.name
+            || self
+                .name
+                .strip_suffix(wanted.name.as_str())
+                .is_some_and(|rest| rest.ends_with(" for "));
+        let kind_matches = match (&self.kind, &wanted.kind) {
+            (Some(kind), Some(wanted)) => {
+                kind == wanted
+                    || (FUNCTION_KINDS.contains(&kind.as_str())
+                        && FUNCTION_KINDS.contains(&wanted.as_str()))
+            }
+            _ => true,
+        };
+        name_matches && kind_matches
+    }
+
+    fn display(&self) -> String {
+        match &self.kind {
+            Some(kind) => format!("{} {}", kind, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// Kind keywords accepted in front of a symbol path segment
+const KEYWORDS: &[&str] = &[
+    "fn",
+    "function",
+    "def",
+    "method",
+    "constructor",
+    "impl",
+    "struct",
+    "enum",
+    "union",
+    "trait",
+    "mod",
+    "const",
+    "static",
+    "type",
+    "macro",
+    "class",
+    "interface",
+    "record",
+    "namespace",
+];
+
+/// Kinds that are interchangeable in queries ("fn bar" finds a Python "def bar")
+const FUNCTION_KINDS: &[&str] = &["fn", "function", "def", "method", "constructor"];
+
+/// How the name of an item is found in its syntax node
+#[derive(Clone, Copy)]
+enum NameRule {
+    /// The `name` field
+    Field,
+    /// The innermost `declarator` (C / C++ functions), possibly qualified
+    Declarator,
+    /// The `type` (and `trait`) fields of a Rust impl
+    Impl,
+    /// A variable whose value is a function (JavaScript / TypeScript)
+    Variable,
+}
+
+/// Node kind, symbol kind and naming rule of each item a language defines
+fn item_rules(language: Language) -> &'static [(&'static str, &'static str, NameRule)] {
+    use NameRule::*;
+    match language {
+        Language::Rust => &[
+            ("function_item", "fn", Field),
+            ("function_signature_item", "fn", Field),
+            ("impl_item", "impl", Impl),
+            ("struct_item", "struct", Field),
+            ("enum_item", "enum", Field),
+            ("union_item", "union", Field),
+            ("trait_item", "trait", Field),
+            ("mod_item", "mod", Field),
+            ("const_item", "const", Field),
+            ("static_item", "static", Field),
+            ("type_item", "type", Field),
+            ("macro_definition", "macro", Field),
+        ],
+        Language::C | Language::Cpp => &[
+            ("function_definition", "function", Declarator),
+            ("struct_specifier", "struct", Field),
+            ("union_specifier", "union", Field),
+            ("enum_specifier", "enum", Field),
+            ("class_specifier", "class", Field),
+            ("namespace_definition", "namespace", Field),
+        ],
+        Language::Java => &[
+            ("class_declaration", "class", Field),
+            ("interface_declaration", "interface", Field),
+            ("enum_declaration", "enum", Field),
+            ("record_declaration", "record", Field),
+            ("method_declaration", "method", Field),
+            ("constructor_declaration", "constructor", Field),
+        ],
+        Language::JavaScript | Language::TypeScript => &[
+            ("function_declaration", "function", Field),
+            ("generator_function_declaration", "function", Field),
+            ("function_signature", "function", Field),
+            ("class_declaration", "class", Field),
+            ("abstract_class_declaration", "class", Field),
+            ("method_definition", "method", Field),
+            ("method_signature", "method", Field),
+            ("abstract_method_signature", "method", Field),
+            ("interface_declaration", "interface", Field),
+            ("type_alias_declaration", "type", Field),
+            ("enum_declaration", "enum", Field),
+            ("internal_module", "namespace", Field),
+            ("variable_declarator", "function", Variable),
+        ],
+        Language::Python => &[
+            ("function_definition", "def", Field),
+            ("class_definition", "class", Field),
+        ],
+        _ => &[],
+    }
+}
+
+/// Nodes that wrap an item and belong to it (decorators, `export`, `template<...>`)
+const WRAPPER_KINDS: &[&str] = &[
+    "decorated_definition",
+    "export_statement",
+    "template_declaration",
+];
+
+/// Every named item in `content`, in file order (enclosing items first)
+///
+/// # Arguments
+/// * `content` - Source code
+/// * `language` - Language of `content`, which selects the grammar
+///
+/// # Returns
+/// * `Ok(Vec<Symbol>)` - The items found (parse errors leave the rest of the file usable)
+/// * `Err(EditError::InvalidEditSpec)` - There is no grammar for `language`
+pub fn symbols(content: &str, language: Language) -> Result<Vec<Symbol>, EditError> {
+    if !language.has_grammar() {
+        return Err(EditError::InvalidEditSpec(format!(
+            "symbol addressing is not supported for {} files (no grammar)",
+            language
+        )));
+    }
+    let tree = syntax::parse(content, language).ok_or_else(|| {
+        EditError::InvalidEditSpec(format!("{} content could not be parsed", language))
+    })?;
+    let mut found = Vec::new();
+    collect(tree.root_node(), content, language, &[], 0, &mut found);
+    Ok(found)
+}
+
+/// Find the single symbol addressed by `query`
+///
+/// A query is a path of segments separated by `::` or `.`, each optionally
+/// prefixed with a kind keyword: `"impl Foo::bar"`, `"Foo.bar"`, `"fn main"`.
+/// Leading segments may be left out as long as the rest is unique; a fully
+/// qualified match wins over partial ones.
+///
+/// # Returns
+/// * `Ok(Symbol)` - The addressed symbol
+/// * `Err(EditError::SymbolNotFound)` - No match; candidates are symbols with a similar name
+/// * `Err(EditError::SymbolAmbiguous)` - Several matches, all listed as candidates
+/// * `Err(EditError::InvalidEditSpec)` - Empty query, or no grammar for `language`
+pub fn find_symbol(content: &str, language: Language, query: &str) -> Result<Symbol, EditError> {
+    let segments = parse_query(query);
+    let Some(wanted) = segments.last() else {
+        return Err(EditError::InvalidEditSpec(
+            "symbol path is empty".to_string(),
+        ));
+    };
+    let all = symbols(content, language)?;
+
+    let matches: Vec<(usize, &Symbol)> = all
+        .iter()
+        .filter_map(|symbol| symbol.matches(&segments).map(|skipped| (skipped, symbol)))
+        .collect();
+    let exact: Vec<&Symbol> = matches
+        .iter()
+        .filter(|(skipped, _)| *skipped == 0)
+        .map(|(_, s)| *s)
+        .collect();
+    match (matches.len(), exact.len()) {
+        (1, _) => return Ok(matches[0].1.clone()),
+        (_, 1) => return Ok(exact[0].clone()),
+        (0, _) => {}
+        _ => {
+            return Err(EditError::SymbolAmbiguous {
+                symbol: query.to_string(),
+                candidates: matches
+                    .into_iter()
+                    .map(|(_, symbol)| symbol.clone())
+                    .collect(),
+            });
+        }
+    }
+
+    // Nothing matched: suggest same-named items, then names containing the wanted one
+    let lowercase = wanted.name.to_lowercase();
+    let mut candidates: Vec<Symbol> = all
+        .iter()
+        .filter(|symbol| symbol.name == wanted.name)
+        .cloned()
+        .collect();
+    if candidates.is_empty() {
+        candidates = all
+            .iter()
+            .filter(|symbol| symbol.name.to_lowercase().contains(&lowercase))
+            .cloned()
+            .collect();
+    }
+    Err(EditError::SymbolNotFound {
+        symbol: query.to_string(),
+        candidates,
+    })
+}
+
+/// Split a symbol path on `::` and `.`
+fn parse_query(query: &str) -> Vec<Segment> {
+    query
+        .split("::")
+        .flat_map(|part| part.split('.'))
+        .filter(|part| !part.trim().is_empty())
+        .map(Segment::parse)
+        .collect()
+}
+
+/// Walk the tree, recording every item with the segments of its enclosing items
+fn collect(
+    node: Node,
+    content: &str,
+    language: Language,
+    scope: &[Segment],
+    depth: usize,
+    found: &mut Vec<Symbol>,
+) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match symbol_at(child, content, language, scope) {
+            Some(mut symbol) => {
+                symbol.depth = depth;
+                let inner = symbol.segments.clone();
+                found.push(symbol);
+                collect(child, content, language, &inner, depth + 1, found);
+            }
+            None => collect(child, content, language, scope, depth, found),
+        }
+    }
+}
+
+/// The symbol defined by `node`, if it is a named item
+fn symbol_at(node: Node, content: &str, language: Language, scope: &[Segment]) -> Option<Symbol> {
+    let &(_, kind, rule) = item_rules(language)
+        .iter()
+        .find(|(node_kind, _, _)| *node_kind == node.kind())?;
+    let text = |node: Node| content[node.byte_range()].to_string();
+
+    let mut segments = scope.to_vec();
+    let mut body = node.child_by_field_name("body");
+    let (name_node, name) = match rule {
+        NameRule::Field => {
+            let name_node = node.child_by_field_name("name")?;
+            (name_node, text(name_node))
+        }
+        NameRule::Declarator => {
+            let mut declarator = node.child_by_field_name("declarator")?;
+            while let Some(inner) = declarator.child_by_field_name("declarator").or_else(|| {
+                (declarator.kind() == "reference_declarator")
+                    .then(|| declarator.named_child(0))
+                    .flatten()
+            }) {
+                declarator = inner;
+            }
+            // Out-of-line definitions ("void Foo::bar()") live in the scope they name
+            while declarator.kind() == "qualified_identifier" {
+                if let Some(scope) = declarator.child_by_field_name("scope") {
+                    segments.push(Segment {
+                        kind: None,
+                        name: base_name(&text(scope)),
+                    });
+                }
+                declarator = declarator.child_by_field_name("name")?;
+            }
+            (declarator, text(declarator))
+        }
+        NameRule::Impl => {
+            let type_node = node.child_by_field_name("type")?;
+            let name = match node.child_by_field_name("trait") {
+                Some(trait_node) => format!(
+                    "{} for {}",
+                    base_name(&text(trait_node)),
+                    base_name(&text(type_node))
+                ),
+                None => base_name(&text(type_node)),
+            };
+            (type_node, name)
+        }
+        NameRule::Variable => {
+            let value = node.child_by_field_name("value")?;
+            if !matches!(
+                value.kind(),
+                "arrow_function" | "function_expression" | "function" | "generator_function"
+            ) {
+                return None;
+            }
+            body = value.child_by_field_name("body");
+            let name_node = node.child_by_field_name("name")?;
+            (name_node, text(name_node))
+        }
+    };
+
+    // The whole item includes its wrappers, and a sole variable declarator its declaration
+    let mut item = node;
+    if let Some(parent) = item
+        .parent()
+        .filter(|parent| matches!(rule, NameRule::Variable) && parent.named_child_count() == 1)
+    {
+        item = parent;
+    }
+    while let Some(parent) = item
+        .parent()
+        .filter(|parent| WRAPPER_KINDS.contains(&parent.kind()))
+    {
+        item = parent;
+    }
+
+    segments.push(Segment {
+        kind: Some(kind.to_string()),
+        name: name.clone(),
+    });
+    let separator = match language {
+        Language::Rust | Language::C | Language::Cpp => "::",
+        _ => ".",
+    };
+    Some(Symbol {
+        kind: kind.to_string(),
+        name,
+        path: segments
+            .iter()
+            .map(Segment::display)
+            .collect::<Vec<_>>()
+            .join(separator),
+        span: span_of(item),
+        name_span: span_of(name_node),
+        body: body.map(span_of),
+        signature_end: body
+            .and_then(|body| body.prev_sibling())
+            .map_or(item.end_byte(), |token| token.end_byte()),
+        line_start: item.start_position().row + 1,
+        line_end: item.end_position().row + 1,
+        depth: 0,
+        segments,
+    })
+}
+
+fn span_of(node: Node) -> Span {
+    Span {
+        byte_start: node.start_byte(),
+        byte_end: node.end_byte(),
+    }
+}
+
+/// Last path component of a type, without generic arguments: `fmt::Display<T>` -> `Display`
+fn base_name(text: &str) -> String {
+    let text = text.split('<').next().unwrap_or(text);
+    text.rsplit("::").next().unwrap_or(text).trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUST: &str = concat!(
+        "struct Foo;\n",
+        "\n",
+        "impl Foo {\n",
+        "    fn new() -> Self { Foo }\n",
+        "    fn bar(&self) -> u32 {\n",
+        "        1\n",
+        "    }\n",
+        "}\n",
+        "\n",
+        "impl std::fmt::Display for Foo {\n",
+        "    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result { Ok(()) }\n",
+        "}\n",
+        "\n",
+        "struct Bar;\n",
+        "impl Bar {\n",
+        "    fn new() -> Self { Bar }\n",
+        "}\n",
+        "\n",
+        "fn new() {}\n",
+    );
+
+    fn target_text<'a>(
+        content: &'a str,
+        language: Language,
+        query: &str,
+        target: SymbolTarget,
+    ) -> &'a str {
+        let span = find_symbol(content, language, query)
+            .unwrap()
+            .target_span(target)
+            .unwrap();
+        &content[span.byte_start..span.byte_end]
+    }
+
+    #[test]
+    fn test_find_rust_symbols() {
+        assert_eq!(
+            target_text(RUST, Language::Rust, "impl Foo::bar", SymbolTarget::Body),
+            "{\n        1\n    }"
+        );
+        assert_eq!(
+            target_text(RUST, Language::Rust, "Foo::bar", SymbolTarget::Signature),
+            "fn bar(&self) -> u32"
+        );
+        assert_eq!(
+            target_text(RUST, Language::Rust, "Foo::fn new", SymbolTarget::Whole),
+            "fn new() -> Self { Foo }"
+        );
+        assert_eq!(
+            target_text(
+                RUST,
+                Language::Rust,
+                "Display for Foo::fmt",
+                SymbolTarget::Name
+            ),
+            "fmt"
+        );
+
+        let symbol = find_symbol(RUST, Language::Rust, "Foo::fmt").unwrap();
+        assert_eq!(symbol.path, "impl Display for Foo::fn fmt");
+        assert_eq!((symbol.line_start, symbol.line_end), (11, 11));
+
+        // A fully qualified match wins over nested ones with the same name
+        assert_eq!(
+            find_symbol(RUST, Language::Rust, "new").unwrap().path,
+            "fn new"
+        );
+    }
+
+    #[test]
+    fn test_symbol_mismatch() {
+        match find_symbol(RUST, Language::Rust, "impl Baz::new") {
+            Err(EditError::SymbolNotFound { candidates, .. }) => {
+                let paths: Vec<&str> = candidates.iter().map(|c| c.path.as_str()).collect();
+                assert_eq!(
+                    paths,
+                    vec!["impl Foo::fn new", "impl Bar::fn new", "fn new"]
+                );
+            }
+            other => panic!("Expected EditError::SymbolNotFound, got {:?}", other),
+        }
+        match find_symbol(RUST, Language::Rust, "impl::new") {
+            Err(EditError::SymbolNotFound { .. }) => {}
+            other => panic!("Expected EditError::SymbolNotFound, got {:?}", other),
+        }
+
+        let source = "mod m { impl Foo { fn new() {} } }\nimpl Bar { fn new() {} }\n";
+        match find_symbol(source, Language::Rust, "new") {
+            Err(EditError::SymbolAmbiguous { candidates, .. }) => {
+                assert_eq!(candidates[0].path, "mod m::impl Foo::fn new");
+            }
+            other => panic!("Expected EditError::SymbolAmbiguous, got {:?}", other),
+        }
+
+        assert!(matches!(
+            find_symbol("x", Language::Unknown, "x"),
+            Err(EditError::InvalidEditSpec(_))
+        ));
+    }
+
+    #[test]
+    fn test_find_symbols_in_other_languages() {
+        let python = "class Foo:\n    @staticmethod\n    def bar():\n        return 1\n";
+        assert_eq!(
+            target_text(python, Language::Python, "Foo.bar", SymbolTarget::Body),
+            "return 1"
+        );
+        assert!(
+            target_text(
+                python,
+                Language::Python,
+                "class Foo::def bar",
+                SymbolTarget::Whole
+            )
+            .starts_with("@staticmethod")
+        );
+
+        let cpp = "namespace ns {\nclass Foo { int bar() { return 1; } };\n}\nint ns::Foo::baz() { return 2; }\n";
+        assert_eq!(
+            find_symbol(cpp, Language::Cpp, "Foo::bar").unwrap().path,
+            "namespace ns::class Foo::function bar"
+        );
+        assert_eq!(
+            target_text(cpp, Language::Cpp, "Foo::baz", SymbolTarget::Body),
+            "{ return 2; }"
+        );
+
+        let js = "export const add = (a, b) => {\n  return a + b;\n};\nclass A { run() {} }\n";
+        assert_eq!(
+            target_text(js, Language::JavaScript, "add", SymbolTarget::Whole),
+            "export const add = (a, b) => {\n  return a + b;\n};"
+        );
+        assert_eq!(
+            target_text(js, Language::JavaScript, "A.run", SymbolTarget::Name),
+            "run"
+        );
+
+        let java = "class A {\n  A() {}\n  void run() {}\n}\n";
+        assert_eq!(
+            target_text(java, Language::Java, "A.run", SymbolTarget::Body),
+            "{}"
+        );
+    }
+}
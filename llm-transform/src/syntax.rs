@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use tree_sitter::{Node, Parser, Tree};
+
+use crate::language::Language;
+use crate::position::{LineIndex, Position, PositionEncoding, Span};
+
+/// Tree-sitter grammar for a language, if one is bundled
+pub(crate) fn grammar(language: Language) -> Option<tree_sitter::Language> {
+    let grammar = match language {
+        Language::Rust => tree_sitter_rust::LANGUAGE,
+        Language::C => tree_sitter_c::LANGUAGE,
+        Language::Cpp => tree_sitter_cpp::LANGUAGE,
+        Language::Java => tree_sitter_java::LANGUAGE,
+        Language::JavaScript => tree_sitter_javascript::LANGUAGE,
+        Language::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT,
+        Language::Python => tree_sitter_python::LANGUAGE,
+        _ => return None,
+    };
+    Some(grammar.into())
+}
+
+/// Parse `content` with the grammar for `language`
+///
+/// TypeScript is parsed as TSX if the plain grammar reports errors that the
+/// TSX one does not, since `.ts` and `.tsx` files share a `Language`.
+///
+/// # Returns
+/// * `Some(Tree)` - The syntax tree (possibly with error nodes)
+/// * `None` - No grammar for the language
+pub(crate) fn parse(content: &str, language: Language) -> Option<Tree> {
+    let tree = parse_with(content, grammar(language)?)?;
+    let tsx = (language == Language::TypeScript && tree.root_node().has_error())
+        .then(|| parse_with(content, tree_sitter_typescript::LANGUAGE_TSX.into()))
+        .flatten()
+        .filter(|tsx| !tsx.root_node().has_error());
+    Some(tsx.unwrap_or(tree))
+}
+
+/// A parse error in source code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyntaxError {
+    /// What is wrong, e.g. `missing "}"` or `unexpected "let"`
+    pub message: String,
+    /// Bytes covered by the error (empty for a missing token)
+    pub span: Span,
+    /// Where the error starts (columns in bytes)
+    pub start: Position,
+    /// Where the error ends (columns in bytes)
+    pub end: Position,
+}
+
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.start.line, self.start.column
+        )
+    }
+}
+
+/// Parse errors in `content`, in file order
+///
+/// Error nodes are reported as a whole (the errors tree-sitter recovered
+/// from inside them are not listed separately), and missing tokens as an
+/// empty span where the parser expected them.
+///
+/// # Arguments
+/// * `content` - The content to parse
+/// * `line_index` - Line index of `content`, for the error positions
+/// * `language` - Grammar to parse with
+///
+/// # Returns
+/// * `Some(Vec<SyntaxError>)` - The errors (empty if the content parses cleanly)
+/// * `None` - No grammar for `language`
+pub fn syntax_errors(
+    content: &str,
+    line_index: &LineIndex,
+    language: Language,
+) -> Option<Vec<SyntaxError>> {
+    let tree = parse(content, language)?;
+    let mut errors = Vec::new();
+    collect_errors(tree.root_node(), content, line_index, &mut errors);
+    Some(errors)
+}
+
+/// Errors in `edited` that are not in `original`
+///
+/// Tree-sitter's error recovery can grow or shrink an error node when code
+/// around it changes, so an error counts as already there if it overlaps
+/// (or touches) an error of the original, moved by `map_offset` from
+/// `original` to `edited` coordinates.
+pub(crate) fn new_syntax_errors(
+    original: &str,
+    original_index: &LineIndex,
+    edited: &str,
+    edited_index: &LineIndex,
+    language: Language,
+    map_offset: impl Fn(usize) -> usize,
+) -> Vec<SyntaxError> {
+    let Some(after) = syntax_errors(edited, edited_index, language) else {
+        return Vec::new();
+    };
+    if after.is_empty() {
+        return after;
+    }
+    let before: Vec<Span> = syntax_errors(original, original_index, language)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|error| Span {
+            byte_start: map_offset(error.span.byte_start),
+            byte_end: map_offset(error.span.byte_end),
+        })
+        .collect();
+    after
+        .into_iter()
+        .filter(|error| {
+            !before.iter().any(|old| {
+                old.byte_start <= error.span.byte_end && error.span.byte_start <= old.byte_end
+            })
+        })
+        .collect()
+}
+
+fn collect_errors(
+    node: Node,
+    content: &str,
+    line_index: &LineIndex,
+    errors: &mut Vec<SyntaxError>,
+) {
+    let message = if node.is_error() {
+        let text = content[node.byte_range()].trim();
+        let mut snippet: String = text
+            .lines()
+            .next()
+            .unwrap_or("")
+            .chars()
+            .take(MAX_ERROR_TEXT)
+            .collect();
+        if snippet.len() < text.len() {
+            snippet.push_str("...");
+        }
+        Some(format!("unexpected {:?}", snippet))
+    } else if node.is_missing() {
+        Some(format!("missing {:?}", node.kind()))
+    } else {
+        None
+    };
+    if let Some(message) = message {
+        let position =
+            |offset| line_index.byte_to_position(content, offset, PositionEncoding::Utf8);
+        errors.push(SyntaxError {
+            message,
+            span: Span {
+                byte_start: node.start_byte(),
+                byte_end: node.end_byte(),
+            },
+            start: position(node.start_byte()),
+            end: position(node.end_byte()),
+        });
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor).filter(|child| child.has_error()) {
+        collect_errors(child, content, line_index, errors);
+    }
+}
+
+/// Characters of an error node's text quoted in its message
+const MAX_ERROR_TEXT: usize = 40;
+
+fn parse_with(content: &str, grammar: tree_sitter::Language) -> Option<Tree> {
+    let mut parser = Parser::new();
+    parser.set_language(&grammar).ok()?;
+    parser.parse(content, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syntax_errors() {
+        let syntax_errors =
+            |content: &str, language| syntax_errors(content, &LineIndex::new(content), language);
+        assert_eq!(
+            syntax_errors("fn main() {}\n", Language::Rust),
+            Some(Vec::new())
+        );
+        assert_eq!(syntax_errors("anything", Language::Unknown), None);
+
+        let errors = syntax_errors("def f(:\n    return 1\n", Language::Python).unwrap();
+        assert_eq!(errors[0].message, "missing \")\"");
+        assert_eq!(errors[0].start, Position { line: 1, column: 7 });
+
+        let errors = syntax_errors("fn a() {\n    let x = ;\n}\n", Language::Rust).unwrap();
+        assert_eq!(errors[0].message, "unexpected \"=\"");
+        assert_eq!(
+            (errors[0].start, errors[0].end),
+            (
+                Position {
+                    line: 2,
+                    column: 11
+                },
+                Position {
+                    line: 2,
+                    column: 12
+                }
+            )
+        );
+
+        let errors = syntax_errors("int main() {\n  return 0;\n", Language::C).unwrap();
+        assert_eq!(errors[0].message, "missing \"}\"");
+        assert_eq!(errors[0].span.byte_start, errors[0].span.byte_end);
+    }
+}
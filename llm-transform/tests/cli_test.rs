@@ -1,7 +1,7 @@
-use std::process::Command;
+use std::env;
 use std::fs;
 use std::path::PathBuf;
-use std::env;
+use std::process::Command;
 
 /// Get the path to the llm-transform binary
 fn bin_path() -> PathBuf {
@@ -23,7 +23,8 @@ fn bin_path() -> PathBuf {
             PathBuf::from("../../target/debug/llm-transform"),
         ];
 
-        paths.into_iter()
+        paths
+            .into_iter()
             .find(|p| p.exists())
             .expect("Could not find llm-transform binary. Please run 'cargo build' first.")
     }
@@ -40,9 +41,18 @@ fn fixtures_dir() -> PathBuf {
     }
 }
 
+/// Copy a fixture into the temp directory so edits can be written back safely
+///
+/// `tag` keeps copies from parallel tests apart.
+fn copy_fixture(name: &str, tag: &str) -> PathBuf {
+    let dest = env::temp_dir().join(format!("llm_transform_{}_{}", tag, name));
+    fs::copy(fixtures_dir().join(name), &dest).expect("Failed to copy fixture");
+    dest
+}
+
 #[test]
 fn test_single_edit_apply() {
-    let sample_file = fixtures_dir().join("sample.rs");
+    let sample_file = copy_fixture("sample.rs", "single_edit_apply");
     let edits_file = fixtures_dir().join("edits.json");
 
     // Run the binary
@@ -55,21 +65,35 @@ fn test_single_edit_apply() {
         .expect("Failed to execute binary");
 
     // Check exit code
-    assert!(output.status.success(), "Binary failed: {:?}", String::from_utf8_lossy(&output.stderr));
+    assert!(
+        output.status.success(),
+        "Binary failed: {:?}",
+        String::from_utf8_lossy(&output.stderr)
+    );
 
     // Check output contains expected text
     let stdout = String::from_utf8_lossy(&output.stdout);
-    assert!(stdout.contains("Applied 1 edit(s)"), "Unexpected output: {}", stdout);
-    assert!(stdout.contains("Final checksum:"), "Missing checksum in output");
+    assert!(
+        stdout.contains("Applied 1 edit(s)"),
+        "Unexpected output: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("Final checksum:"),
+        "Missing checksum in output"
+    );
 
     // Verify the checksum changed
     let original_checksum = "a799a184979630901ec8170adc49fc3f9297125ceb4ef4af73b5cc7c4da7ff88";
-    assert!(!stdout.contains(original_checksum), "Checksum should have changed after edit");
+    assert!(
+        !stdout.contains(original_checksum),
+        "Checksum should have changed after edit"
+    );
 }
 
 #[test]
 fn test_multiple_edits_apply() {
-    let sample_file = fixtures_dir().join("sample.rs");
+    let sample_file = copy_fixture("sample.rs", "multiple_edits_apply");
     let edits_file = fixtures_dir().join("edits_multiple.json");
 
     // Run the binary
@@ -82,17 +106,28 @@ fn test_multiple_edits_apply() {
         .expect("Failed to execute binary");
 
     // Check exit code
-    assert!(output.status.success(), "Binary failed: {:?}", String::from_utf8_lossy(&output.stderr));
+    assert!(
+        output.status.success(),
+        "Binary failed: {:?}",
+        String::from_utf8_lossy(&output.stderr)
+    );
 
     // Check output
     let stdout = String::from_utf8_lossy(&output.stdout);
-    assert!(stdout.contains("Applied 2 edit(s)"), "Unexpected output: {}", stdout);
-    assert!(stdout.contains("Final checksum:"), "Missing checksum in output");
+    assert!(
+        stdout.contains("Applied 2 edit(s)"),
+        "Unexpected output: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("Final checksum:"),
+        "Missing checksum in output"
+    );
 }
 
 #[test]
 fn test_checksum_mismatch() {
-    let sample_file = fixtures_dir().join("sample.rs");
+    let sample_file = copy_fixture("sample.rs", "checksum_mismatch");
     let edits_file = fixtures_dir().join("edits_wrong_checksum.json");
 
     // Run the binary
@@ -105,17 +140,23 @@ fn test_checksum_mismatch() {
         .expect("Failed to execute binary");
 
     // Should fail with checksum mismatch
-    assert!(!output.status.success(), "Binary should have failed with checksum mismatch");
+    assert!(
+        !output.status.success(),
+        "Binary should have failed with checksum mismatch"
+    );
 
     // Check error message (goes to stdout in current implementation)
     let stdout = String::from_utf8_lossy(&output.stdout);
-    assert!(stdout.contains("Checksum mismatch") || stdout.contains("checksum"),
-            "Expected checksum error, got: {}", stdout);
+    assert!(
+        stdout.contains("Checksum mismatch") || stdout.contains("checksum"),
+        "Expected checksum error, got: {}",
+        stdout
+    );
 }
 
 #[test]
 fn test_json_output() {
-    let sample_file = fixtures_dir().join("sample.rs");
+    let sample_file = copy_fixture("sample.rs", "json_output");
     let edits_file = fixtures_dir().join("edits.json");
 
     // Run the binary with --json flag
@@ -129,23 +170,36 @@ fn test_json_output() {
         .expect("Failed to execute binary");
 
     // Check exit code
-    assert!(output.status.success(), "Binary failed: {:?}", String::from_utf8_lossy(&output.stderr));
+    assert!(
+        output.status.success(),
+        "Binary failed: {:?}",
+        String::from_utf8_lossy(&output.stderr)
+    );
 
     // Check output is valid JSON
     let stdout = String::from_utf8_lossy(&output.stdout);
-    let json: serde_json::Value = serde_json::from_str(&stdout)
-        .expect("Output should be valid JSON");
+    let json: serde_json::Value =
+        serde_json::from_str(&stdout).expect("Output should be valid JSON");
 
     // Verify JSON structure
-    assert!(json["success"].as_bool().unwrap(), "JSON should indicate success");
-    assert!(json["final_checksum"].is_string(), "JSON should have final_checksum");
-    assert!(json["applied_count"].is_number(), "JSON should have applied_count");
+    assert!(
+        json["success"].as_bool().unwrap(),
+        "JSON should indicate success"
+    );
+    assert!(
+        json["final_checksum"].is_string(),
+        "JSON should have final_checksum"
+    );
+    assert!(
+        json["applied_count"].is_number(),
+        "JSON should have applied_count"
+    );
     assert_eq!(json["applied_count"], 1, "Should have applied 1 edit");
 }
 
 #[test]
 fn test_stdin_input() {
-    let sample_file = fixtures_dir().join("sample.rs");
+    let sample_file = copy_fixture("sample.rs", "stdin_input");
     let _edits_json = r#"{
       "execution_id": "test-execution-stdin",
       "expected_checksum": "a799a184979630901ec8170adc49fc3f9297125ceb4ef4af73b5cc7c4da7ff88",
@@ -172,7 +226,7 @@ fn test_stdin_input() {
 
 #[test]
 fn test_file_output() {
-    let sample_file = fixtures_dir().join("sample.rs");
+    let sample_file = copy_fixture("sample.rs", "file_output");
     let edits_file = fixtures_dir().join("edits.json");
     let output_file = "/tmp/test_output.txt";
 
@@ -191,19 +245,29 @@ fn test_file_output() {
         .expect("Failed to execute binary");
 
     // Check exit code
-    assert!(output.status.success(), "Binary failed: {:?}", String::from_utf8_lossy(&output.stderr));
+    assert!(
+        output.status.success(),
+        "Binary failed: {:?}",
+        String::from_utf8_lossy(&output.stderr)
+    );
 
     // Check output file was created
-    assert!(PathBuf::from(output_file).exists(), "Output file should exist");
+    assert!(
+        PathBuf::from(output_file).exists(),
+        "Output file should exist"
+    );
 
     // Read and verify output file content
-    let output_content = fs::read_to_string(output_file)
-        .expect("Failed to read output file");
+    let output_content = fs::read_to_string(output_file).expect("Failed to read output file");
 
-    assert!(output_content.contains("Applied 1 edit(s)"),
-            "Output file should contain edit result");
-    assert!(output_content.contains("Final checksum:"),
-            "Output file should contain checksum");
+    assert!(
+        output_content.contains("Applied 1 edit(s)"),
+        "Output file should contain edit result"
+    );
+    assert!(
+        output_content.contains("Final checksum:"),
+        "Output file should contain checksum"
+    );
 
     // Clean up
     let _ = fs::remove_file(output_file);
@@ -211,7 +275,7 @@ fn test_file_output() {
 
 #[test]
 fn test_json_output_to_file() {
-    let sample_file = fixtures_dir().join("sample.rs");
+    let sample_file = copy_fixture("sample.rs", "json_output_to_file");
     let edits_file = fixtures_dir().join("edits.json");
     let output_file = "/tmp/test_output.json";
 
@@ -231,20 +295,918 @@ fn test_json_output_to_file() {
         .expect("Failed to execute binary");
 
     // Check exit code
-    assert!(output.status.success(), "Binary failed: {:?}", String::from_utf8_lossy(&output.stderr));
+    assert!(
+        output.status.success(),
+        "Binary failed: {:?}",
+        String::from_utf8_lossy(&output.stderr)
+    );
 
     // Check output file was created
-    assert!(PathBuf::from(output_file).exists(), "Output file should exist");
+    assert!(
+        PathBuf::from(output_file).exists(),
+        "Output file should exist"
+    );
 
     // Read and verify output file is valid JSON
-    let output_content = fs::read_to_string(output_file)
-        .expect("Failed to read output file");
+    let output_content = fs::read_to_string(output_file).expect("Failed to read output file");
 
-    let json: serde_json::Value = serde_json::from_str(&output_content)
-        .expect("Output file should contain valid JSON");
+    let json: serde_json::Value =
+        serde_json::from_str(&output_content).expect("Output file should contain valid JSON");
 
-    assert!(json["success"].as_bool().unwrap(), "JSON should indicate success");
+    assert!(
+        json["success"].as_bool().unwrap(),
+        "JSON should indicate success"
+    );
 
     // Clean up
     let _ = fs::remove_file(output_file);
 }
+
+#[test]
+fn test_edit_written_back() {
+    let sample_file = copy_fixture("sample.rs", "edit_written_back");
+    let edits_file = fixtures_dir().join("edits.json");
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .arg("--json")
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {:?}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The edit replaces "Hello" with "Hi" on disk
+    let written = fs::read_to_string(&sample_file).expect("Failed to read edited file");
+    assert!(
+        written.contains("format!(\"Hi, {}!\", name)"),
+        "Edit not written back: {}",
+        written
+    );
+
+    // The reported checksum matches the file on disk
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json: serde_json::Value =
+        serde_json::from_str(&stdout).expect("Output should be valid JSON");
+    let on_disk = blake3::hash(written.as_bytes()).to_hex().to_string();
+    assert_eq!(json["final_checksum"].as_str().unwrap(), on_disk);
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_checksum_mismatch_leaves_file_unchanged() {
+    let sample_file = copy_fixture("sample.rs", "mismatch_unchanged");
+    let edits_file = fixtures_dir().join("edits_wrong_checksum.json");
+    let original = fs::read_to_string(&sample_file).unwrap();
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        !output.status.success(),
+        "Binary should have failed with checksum mismatch"
+    );
+    assert_eq!(fs::read_to_string(&sample_file).unwrap(), original);
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_partial_failure_reports_nothing_applied() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let sample_file = copy_fixture("sample.rs", "partial_failure");
+    let original = fs::read_to_string(&sample_file).unwrap();
+    let request = r#"{
+      "execution_id": "test-partial-failure",
+      "file_path": "sample.rs",
+      "expected_checksum": "a799a184979630901ec8170adc49fc3f9297125ceb4ef4af73b5cc7c4da7ff88",
+      "edits": [
+        {"byte_start": 0, "byte_end": 2, "replacement": "fn"},
+        {"byte_start": 10, "byte_end": 5, "replacement": "x"},
+        {"byte_start": 68, "byte_end": 70, "replacement": "fn"}
+      ]
+    }"#;
+
+    let mut child = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--json")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute binary");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(request.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().expect("Failed to wait for binary");
+
+    assert!(!output.status.success(), "Binary should have failed");
+    assert_eq!(fs::read_to_string(&sample_file).unwrap(), original);
+
+    // Nothing was written, so no edit is reported as applied; results follow request order
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output should be valid JSON");
+    let statuses: Vec<&str> = json["edits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(statuses, ["rolled_back", "error", "rolled_back"]);
+    let offsets: Vec<u64> = json["edits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["byte_offset"].as_u64().unwrap())
+        .collect();
+    assert_eq!(offsets, [0, 10, 68]);
+    assert_eq!(json["applied_count"], 0);
+    assert_eq!(json["rolled_back_count"], 2);
+    assert_eq!(json["error_count"], 1);
+    assert_eq!(json["total_byte_shift"], 0);
+    assert_eq!(
+        json["final_checksum"],
+        "a799a184979630901ec8170adc49fc3f9297125ceb4ef4af73b5cc7c4da7ff88"
+    );
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_transactional_rollback() {
+    let sample_file = copy_fixture("sample.rs", "transactional_rollback");
+    let edits_file = fixtures_dir().join("edits_transactional.json");
+    let original = fs::read_to_string(&sample_file).unwrap();
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .arg("--json")
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        !output.status.success(),
+        "Binary should fail when a transactional edit fails"
+    );
+    assert_eq!(fs::read_to_string(&sample_file).unwrap(), original);
+
+    // Every edit is reported with its final status, in request order
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json: serde_json::Value =
+        serde_json::from_str(&stdout).expect("Output should be valid JSON");
+    assert_eq!(json["error_count"], 1);
+    assert_eq!(json["rolled_back_count"], 1);
+    assert_eq!(json["edits"][0]["status"], "rolled_back");
+    assert_eq!(json["edits"][1]["status"], "error");
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_any_checksum_with_expected_text() {
+    let sample_file = copy_fixture("sample.rs", "any_checksum");
+    let edits_file = fixtures_dir().join("edits_any_checksum.json");
+
+    // Change the file away from the edit so the whole-file checksum no longer matches
+    let mut content = fs::read_to_string(&sample_file).unwrap();
+    content.push_str("\n// trailing comment\n");
+    fs::write(&sample_file, &content).unwrap();
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let written = fs::read_to_string(&sample_file).unwrap();
+    assert!(written.contains("format!(\"Hi, {}!\", name)"));
+    assert!(written.ends_with("// trailing comment\n"));
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_line_column_addressing() {
+    let sample_file = copy_fixture("sample.rs", "line_column");
+    let edits_file = fixtures_dir().join("edits_line_column.json");
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let written = fs::read_to_string(&sample_file).unwrap();
+    assert!(written.contains("format!(\"Hi, {}!\", name)"));
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_line_out_of_range() {
+    let sample_file = copy_fixture("sample.rs", "line_out_of_range");
+    let edits_file = env::temp_dir().join("llm_transform_line_out_of_range.json");
+    let original = fs::read_to_string(&sample_file).unwrap();
+    fs::write(
+        &edits_file,
+        r#"{"file_path": "sample.rs", "expected_checksum": "any", "edits": [
+            {"line_start": 999, "col_start": 1, "line_end": 999, "col_end": 1, "replacement": "x"}
+        ]}"#,
+    )
+    .unwrap();
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Line 999 out of range"),
+        "Unexpected output: {}",
+        stdout
+    );
+    assert_eq!(fs::read_to_string(&sample_file).unwrap(), original);
+
+    let _ = fs::remove_file(&sample_file);
+    let _ = fs::remove_file(&edits_file);
+}
+
+#[test]
+fn test_find_edits() {
+    let sample_file = copy_fixture("sample.rs", "find_edits");
+    let edits_file = fixtures_dir().join("edits_find.json");
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let written = fs::read_to_string(&sample_file).unwrap();
+    assert!(written.contains("greet(\"Rust\")"));
+    assert!(written.contains("fn main() {\n    // entry point\n    println!"));
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_find_not_unique() {
+    let sample_file = copy_fixture("sample.rs", "find_not_unique");
+    let edits_file = env::temp_dir().join("llm_transform_find_not_unique.json");
+    let original = fs::read_to_string(&sample_file).unwrap();
+    fs::write(
+        &edits_file,
+        r#"{"file_path": "sample.rs", "expected_checksum": "any", "edits": [
+            {"kind": "find", "find": "name", "replacement": "who"}
+        ]}"#,
+    )
+    .unwrap();
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .arg("--json")
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(!output.status.success());
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(
+        response["error"]
+            .as_str()
+            .unwrap()
+            .contains("is not unique: 2 matches")
+    );
+    assert_eq!(response["edits"][0]["status"], "error");
+    assert_eq!(
+        response["edits"][0]["candidates"].as_array().unwrap().len(),
+        2
+    );
+    assert_eq!(fs::read_to_string(&sample_file).unwrap(), original);
+
+    let _ = fs::remove_file(&sample_file);
+    let _ = fs::remove_file(&edits_file);
+}
+
+#[test]
+fn test_regex_replace() {
+    let sample_file = copy_fixture("sample.rs", "regex_replace");
+    let edits_file = fixtures_dir().join("edits_regex.json");
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .arg("--json")
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["applied_count"], 2);
+    assert_eq!(response["edits"].as_array().unwrap().len(), 2);
+
+    let written = fs::read_to_string(&sample_file).unwrap();
+    assert!(written.contains("fn welcome(name: &str)"));
+    assert!(written.contains("welcome(\"World\")"));
+    assert!(!written.contains("greet"));
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_undo_round_trip() {
+    let sample_file = copy_fixture("sample.rs", "undo_round_trip");
+    let edits_file = fixtures_dir().join("edits_multiple.json");
+    let undo_file = env::temp_dir().join("llm_transform_undo_round_trip.json");
+    let original = fs::read_to_string(&sample_file).unwrap();
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .arg("--json")
+        .output()
+        .expect("Failed to execute binary");
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert_ne!(fs::read_to_string(&sample_file).unwrap(), original);
+
+    // Feed the undo object straight back in
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json: serde_json::Value =
+        serde_json::from_str(&stdout).expect("Output should be valid JSON");
+    assert!(
+        json["undo"].is_object(),
+        "Response should carry an undo request"
+    );
+    fs::write(&undo_file, serde_json::to_string(&json["undo"]).unwrap()).unwrap();
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&undo_file)
+        .output()
+        .expect("Failed to execute binary");
+    assert!(
+        output.status.success(),
+        "Undo failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert_eq!(fs::read_to_string(&sample_file).unwrap(), original);
+
+    let _ = fs::remove_file(&sample_file);
+    let _ = fs::remove_file(&undo_file);
+}
+
+#[test]
+fn test_line_range_edits() {
+    let sample_file = copy_fixture("sample.rs", "line_range_edits");
+    let edits_file = fixtures_dir().join("edits_lines.json");
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert_eq!(
+        fs::read_to_string(&sample_file).unwrap(),
+        "fn greet(name: &str) -> String {\n    format!(\"Hi, {}!\", name)\n}\n/// Entry point\nfn main() {\n    println!(\"{}\", greet(\"World\"));\n}\n"
+    );
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_hunk_edits() {
+    let sample_file = copy_fixture("sample.rs", "hunk_edits");
+    let edits_file = fixtures_dir().join("edits_hunk.json");
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .arg("--json")
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let edits = response["edits"].as_array().unwrap();
+    // Reported in request order: the hunk hinted at line 3 is 2 lines off
+    assert_eq!(edits[0]["line_offset"], 2);
+    assert_eq!(edits[0]["fuzz"], 0);
+    assert_eq!(edits[1]["line_offset"], 0);
+
+    let written = fs::read_to_string(&sample_file).unwrap();
+    assert!(written.contains("format!(\"Hi, {}!\", name)"));
+    assert!(written.contains("greet(\"Hunk\")"));
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_hunk_match_reported_on_its_own_edit() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let sample_file = copy_fixture("sample.rs", "hunk_match_by_index");
+    // An insertion at the very offset the hunk is found at
+    let request = r#"{"file_path": "sample.rs", "expected_checksum": "a799a184979630901ec8170adc49fc3f9297125ceb4ef4af73b5cc7c4da7ff88",
+        "edits": [
+            {"byte_start": 33, "byte_end": 33, "replacement": "    // greeting\n"},
+            {"kind": "hunk", "old_text": "    format!(\"Hello, {}!\", name)\n", "replacement": "    format!(\"Hi, {}!\", name)\n"}
+        ]}"#;
+
+    let mut child = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--json")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn binary");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(request.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().expect("Failed to wait for binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(response["edits"][0].get("fuzz").is_none());
+    assert_eq!(response["edits"][1]["line_offset"], 0);
+    assert_eq!(response["edits"][1]["fuzz"], 0);
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_patch_file() {
+    let sample_file = copy_fixture("sample.rs", "patch_file");
+    let patch_file = fixtures_dir().join("sample.diff");
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--patch")
+        .arg(&patch_file)
+        .arg("--json")
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["applied_count"], 2);
+    assert_eq!(response["edits"][1]["line_offset"], 2);
+
+    let written = fs::read_to_string(&sample_file).unwrap();
+    assert!(written.contains("format!(\"Hi, {}!\", name)"));
+    assert!(written.contains("greet(\"Patch\")"));
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_patch_without_context() {
+    let sample_file = copy_fixture("sample.rs", "patch_without_context");
+    let patch_file = fixtures_dir().join("sample_u0.diff");
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--patch")
+        .arg(&patch_file)
+        .arg("--json")
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["applied_count"], 3);
+
+    // Additions without context are placed by the header's line
+    let written = fs::read_to_string(&sample_file).unwrap();
+    assert_eq!(
+        written,
+        "/// Greets someone\nfn greet(name: &str) -> String {\n    format!(\"Hello, {}!\", name)\n}\n\n\
+         // Entry point\nfn main() {\n    println!(\"{}\", greet(\"U0\"));\n}\n"
+    );
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_patch_from_stdin_reports_each_hunk() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let sample_file = copy_fixture("sample.rs", "patch_from_stdin");
+    let original = fs::read_to_string(&sample_file).unwrap();
+    let diff = "@@ -2 +2 @@\n-    format!(\"Hello, {}!\", name)\n+    format!(\"Hi, {}!\", name)\n\
+                @@ -6 +6 @@\n-    println!(\"{}\", greet(\"Nobody\"));\n+    println!(\"{}\", greet(\"Patch\"));\n";
+
+    let mut child = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--json")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn binary");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(diff.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().expect("Failed to wait for binary");
+
+    assert!(!output.status.success());
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(response["edits"][0]["status"], "skipped");
+    assert_eq!(response["edits"][1]["status"], "error");
+    assert!(
+        response["edits"][1]["reason"]
+            .as_str()
+            .unwrap()
+            .starts_with("Hunk not found")
+    );
+    assert_eq!(fs::read_to_string(&sample_file).unwrap(), original);
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_symbol_edits() {
+    let sample_file = copy_fixture("sample.rs", "symbol_edits");
+    let edits_file = fixtures_dir().join("edits_symbol.json");
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .arg("--json")
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let written = fs::read_to_string(&sample_file).unwrap();
+    assert!(
+        written
+            .starts_with("fn greet(name: &str) -> String {\n    format!(\"Hey, {}!\", name)\n}\n")
+    );
+    assert!(written.contains("\npub fn main() {\n"));
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_symbol_not_found_lists_candidates() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let sample_file = copy_fixture("sample.rs", "symbol_not_found");
+    let request = r#"{"file_path": "sample.rs", "expected_checksum": "any",
+        "edits": [{"symbol": "fn Greet", "expected_text": "", "replacement": ""}]}"#;
+
+    let mut child = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--json")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn binary");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(request.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().expect("Failed to wait for binary");
+
+    assert!(!output.status.success());
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let symbols = response["edits"][0]["symbols"]
+        .as_array()
+        .expect("Result should list candidate symbols");
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0]["path"], "fn greet");
+    assert_eq!(symbols[0]["line_start"], 1);
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_validate_syntax_rejects_broken_edit() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let sample_file = copy_fixture("sample.rs", "validate_syntax");
+    let original = fs::read_to_string(&sample_file).unwrap();
+    // Drops the closing brace of `greet`
+    let request = r#"{"file_path": "sample.rs", "expected_checksum": "any",
+        "edits": [{"kind": "find", "find": "}\n\nfn main", "replacement": "\nfn main"}]}"#;
+
+    let mut child = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--validate-syntax")
+        .arg("--json")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn binary");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(request.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().expect("Failed to wait for binary");
+
+    assert!(!output.status.success());
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(
+        response["error"].as_str().unwrap().contains("syntax error"),
+        "Unexpected response: {}",
+        response
+    );
+    let errors = response["syntax_errors"]
+        .as_array()
+        .expect("Response should list syntax errors");
+    assert!(errors[0]["start"]["line"].as_u64().is_some());
+    assert_eq!(fs::read_to_string(&sample_file).unwrap(), original);
+
+    let _ = fs::remove_file(&sample_file);
+}
+
+#[test]
+fn test_outline_feeds_edit_request() {
+    let sample_file = copy_fixture("sample.rs", "outline");
+
+    let output = Command::new(bin_path())
+        .arg("outline")
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--json")
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let outline: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        outline["expected_checksum"],
+        "a799a184979630901ec8170adc49fc3f9297125ceb4ef4af73b5cc7c4da7ff88"
+    );
+    let items = outline["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[1]["symbol_kind"], "fn");
+    assert_eq!(items[1]["name"], "main");
+    assert_eq!(
+        (
+            items[1]["first_line"].as_u64(),
+            items[1]["last_line"].as_u64()
+        ),
+        (Some(5), Some(7))
+    );
+
+    // An item plus a replacement is an edit
+    let mut edit = items[1].clone();
+    edit["replacement"] = "fn main() {}".into();
+    let request = serde_json::json!({
+        "file_path": outline["file_path"],
+        "expected_checksum": outline["expected_checksum"],
+        "edits": [edit],
+    });
+    let request_file = env::temp_dir().join("llm_transform_outline_request.json");
+    fs::write(&request_file, request.to_string()).unwrap();
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&request_file)
+        .output()
+        .expect("Failed to execute binary");
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert!(
+        fs::read_to_string(&sample_file)
+            .unwrap()
+            .ends_with("}\n\nfn main() {}\n")
+    );
+
+    let _ = fs::remove_file(&sample_file);
+    let _ = fs::remove_file(&request_file);
+}
+
+#[test]
+fn test_diff_output() {
+    let sample_file = copy_fixture("sample.rs", "diff_output");
+    let edits_file = fixtures_dir().join("edits.json");
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .arg("--diff")
+        .arg("--diff-context")
+        .arg("1")
+        .arg("--json")
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let diff = response["diff"]
+        .as_str()
+        .expect("Response should carry a diff");
+    let path = sample_file.display().to_string();
+    assert!(
+        diff.starts_with(&format!("--- {}\n+++ {}\n@@ ", path, path)),
+        "Unexpected diff: {}",
+        diff
+    );
+    assert!(
+        diff.contains("\n-    format!(\"Hello, {}!\", name)\n"),
+        "Unexpected diff: {}",
+        diff
+    );
+
+    // Human-readable mode prints the diff after the summary
+    let human_file = copy_fixture("sample.rs", "diff_output_human");
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&human_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .arg("--diff")
+        .output()
+        .expect("Failed to execute binary");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Total byte shift:"),
+        "Unexpected output: {}",
+        stdout
+    );
+    assert!(stdout.contains("\n\n--- "), "Unexpected output: {}", stdout);
+
+    let _ = fs::remove_file(&sample_file);
+    let _ = fs::remove_file(&human_file);
+}
+
+#[test]
+fn test_dry_run_leaves_file_unchanged() {
+    let sample_file = copy_fixture("sample.rs", "dry_run");
+    let edits_file = fixtures_dir().join("edits.json");
+    let original = fs::read_to_string(&sample_file).unwrap();
+
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .arg("--dry-run")
+        .arg("--print-content")
+        .arg("--json")
+        .output()
+        .expect("Failed to execute binary");
+
+    assert!(
+        output.status.success(),
+        "Binary failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let dry: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(dry["dry_run"], true);
+    assert!(dry["diff"].as_str().is_some_and(|diff| !diff.is_empty()));
+    assert!(dry["undo"].is_object());
+    assert_eq!(
+        fs::read_to_string(&sample_file).unwrap(),
+        original,
+        "Dry run must not write the file"
+    );
+
+    // The real run produces exactly the reported content and checksum
+    let output = Command::new(bin_path())
+        .arg("--file")
+        .arg(&sample_file)
+        .arg("--edits")
+        .arg(&edits_file)
+        .arg("--json")
+        .output()
+        .expect("Failed to execute binary");
+    let real: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(real.get("dry_run").is_none());
+    assert_eq!(dry["final_checksum"], real["final_checksum"]);
+    assert_eq!(
+        dry["final_content"].as_str(),
+        Some(fs::read_to_string(&sample_file).unwrap().as_str())
+    );
+
+    let _ = fs::remove_file(&sample_file);
+}